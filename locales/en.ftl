@@ -0,0 +1,214 @@
+# English messages, also the fallback for every other language.
+
+welcome = You are in a bizarre world full of weird emojis. And what am I doing inside a linux terminal?
+out-of-bounds = Stay with us, don't try to leave
+wandering = You are wandering around!
+troll-blocks-path = There's a troll here! You need a sword to fight!
+troll-defeated = You bravely fight and defeat the troll!
+troll-defeated-loot = You bravely fight and defeat the troll! He leaves an { $item } on the ground!
+spider-defeated = You've defeated the spider!
+spider-defeated-loot = You've defeated the spider and found a { $item } on its body!
+fish-caught = You catch a fish with your harpoon! When you look inside the fish, you find a { $item }.. Looks like the fish had something weird for dinner!
+fish-ignored = You don't want to fish anymore!
+fish-out-of-reach = You see a fish swimming by, but you have no tool to catch it!
+swimming = You are swimming like a cute little fish !
+water-blocked = You cannot enter like this in the water, look around for something that may help you!
+tree-chopped = You chopped a tree!
+chest-uncovered = You chopped a tree... And found a chest!
+tree-blocked = You gonna need a tool if you want to interact with a tree!
+mountain-blocked = The mountain is too steep to climb.
+chest-looted = You found a { $item }, but for what ?
+chest-empty = Chest is empty. You already took the { $item } that was inside.
+castle-opened = You enter the castle and there is a beautiful princess inside, you found love and are virtually happy for the rest of your virtual life. Press Enter to continue...
+castle-locked = Castle door is locked!
+trapped-in-web = It's a trap!!! You are trapped into the spider web, the spider is gonna come back soon and eat you alive. You die. Press Enter to continue...
+unreachable = THIS MESSAGE SHOULD NEVER APPEAR, CONTACT THE GAME DEVELOPER!
+
+game-saved = Game saved.
+save-failed = Could not save the game: { $error }
+
+hud-inventory = Player Inventory:
+hud-time = Time:
+phase-dawn = Dawn
+phase-day = Day
+phase-dusk = Dusk
+phase-night = Night
+hud-weather = Weather:
+weather-clear = Clear
+weather-rain = Rain
+weather-fog = Fog
+weather-storm = Storm
+
+log-title = Message history
+log-help = Up/Down/PgUp/PgDn: scroll   /: search   n/N: next/previous match   Esc: close
+log-search-prompt = Search:
+log-no-match = No message matches "{ $query }"
+
+help-title = Controls
+help-close = Press any key to go back to the game.
+action-move-north = Move north
+action-move-south = Move south
+action-move-west = Move west
+action-move-east = Move east
+action-move-north-west = Move north-west
+action-move-north-east = Move north-east
+action-move-south-west = Move south-west
+action-move-south-east = Move south-east
+action-wait = Wait
+action-message-log = Message history
+action-help = Controls
+action-stats = Statistics and achievements
+action-toggle-panel = Show or hide the help panel
+action-save = Save the game
+action-quit = Quit
+
+menu-title = Rust ASCII RPG
+menu-subtitle = A bizarre world full of weird emojis.
+menu-new-game = New game
+menu-continue = Continue
+menu-load = Load
+menu-settings = Settings
+menu-controls = Controls
+menu-high-scores = High scores
+menu-quit = Quit
+menu-help = Up/Down: choose   Enter: confirm   Esc: quit
+menu-no-save = There is no saved game to continue.
+menu-load-prompt = File to load (savegame .json or map):
+menu-load-failed = Could not load { $path }: { $error }
+
+settings-title = Settings
+settings-tileset = Tileset
+settings-keybindings = Keybindings
+settings-difficulty = Difficulty
+settings-speedrun-timer = Speedrun timer
+settings-on = On
+settings-off = Off
+settings-name = Player name
+settings-help = Up/Down: choose   Left/Right: change   Enter: type the name   Esc: save and go back
+settings-save-failed = Could not save the settings: { $error }
+settings-invalid-keybindings = Invalid keybindings: { $error }
+settings-invalid-balance = Invalid balance: { $error }
+tileset-emoji = Emoji
+tileset-ascii = ASCII
+preset-arrows = Arrow keys
+preset-wasd = WASD
+preset-vi = Vi keys
+difficulty-easy = Easy
+difficulty-normal = Normal
+difficulty-hard = Hard
+
+end-victory = Victory!
+end-defeat = Game over
+end-seed = Seed: { $seed }
+end-turns = Turns taken: { $turns }
+end-items = Items found: { $items }
+end-kills = Creatures defeated: { $kills }
+end-score = Score: { $score }
+end-high-score = New high score: #{ $rank } on this seed!
+end-restart = Play this seed again
+end-new-seed = Play a new seed
+end-main-menu = Back to the main menu
+end-quit = Quit
+end-help = Up/Down: choose   Enter: confirm   Esc: main menu
+
+panel-legend = Legend:
+panel-controls = Controls:
+panel-hint = Hint:
+panel-move = Move
+hint-spider-web = Careful, walking into a spider web is deadly.
+hint-troll = A troll is right next to you, walk into it to fight.
+hint-fish = A fish swims by, you would need a harpoon to catch it.
+hint-castle = The castle door is locked, its key must be hidden somewhere.
+hint-water = The water is deep, you need something to breathe under it.
+hint-tree = These trees could be chopped down with the right tool.
+hint-night = Trolls hunt at night, keep away from them.
+hint-explore = Explore the world and find a way into the castle.
+tile-grass = grass
+tile-tree = tree
+tile-water = water
+tile-mountain = mountain
+tile-sand = sand
+tile-castle = castle
+tile-chest = chest
+tile-spider-web = spider web
+tile-heart = heart
+tile-shallow-water = shallow water
+npc-fish = fish
+npc-troll = troll
+npc-spider = spider
+
+achievement-unlocked = Achievement unlocked: { $name }!
+profile-save-failed = Could not save the profile: { $error }
+stats-title = Statistics
+stats-this-game = This game
+stats-lifetime = All games
+stats-steps = Steps
+stats-tiles-swum = Tiles swum
+stats-trees-chopped = Trees chopped
+stats-trolls-slain = Trolls slain
+stats-deaths-by-web = Deaths by web
+stats-games = Games played
+stats-wins = Wins
+stats-deaths = Deaths
+stats-time-to-win = Turns to win
+stats-achievements = Achievements
+achievement-troll-slayer = Troll slayer
+achievement-troll-slayer-description = Slay 5 trolls in a single game.
+achievement-pacifist = Pacifist
+achievement-pacifist-description = Win slaying only the one troll you need.
+achievement-lumberjack = Lumberjack
+achievement-lumberjack-description = Chop down 100 trees in a single game.
+achievement-swimmer = Swimmer
+achievement-swimmer-description = Swim 100 tiles in a single game.
+achievement-speedrunner = Speedrunner
+achievement-speedrunner-description = Win in 500 turns or less.
+achievement-caught-in-the-web = Caught in the web
+achievement-caught-in-the-web-description = Die in a spider web.
+achievement-veteran = Veteran
+achievement-veteran-description = Win 10 games.
+
+hud-timer = Timer:
+timer-split = Split: { $split } at { $time }
+timer-personal-best = New personal best on this seed!
+timer-not-from-start = The speedrun timer only runs on games played from their first turn.
+splits-load-failed = Could not load the personal best splits: { $error }
+splits-save-failed = Could not save the personal best splits: { $error }
+split-first-troll = First troll
+split-axe = Axe
+split-chest = Chest
+split-harpoon = Harpoon
+split-key = Key
+split-castle = Castle
+
+name-title = Who goes there?
+name-prompt = Your name:
+name-default = Adventurer
+
+scores-title = High scores
+scores-table = Seed { $seed }, { $difficulty } ({ $page }/{ $pages })
+scores-won = won
+scores-died = died
+scores-empty = No scores yet. Finish a game to get on the board.
+scores-help = Left/Right: other seeds   Esc: back
+scores-load-failed = Could not load the high scores: { $error }
+scores-save-failed = Could not save the high scores: { $error }
+replay-save-failed = Could not save the replay: { $error }
+
+script-error = Script error: { $error }
+
+dev-reloaded-balance = Balance reloaded.
+dev-reloaded-content = Rules, scripts and mods reloaded.
+dev-reloaded-theme = Tileset reloaded.
+dev-reload-failed = Reload failed: { $error }
+
+bot-goal-snorkel = Bot: after a spider, for its snorkel.
+bot-goal-axe = Bot: after a troll, for its axe.
+bot-goal-harpoon = Bot: chopping trees to find the chest.
+bot-goal-key = Bot: fishing for the key.
+bot-goal-castle = Bot: heading for the castle.
+
+item-sword = sword
+item-axe = axe
+item-harpoon = harpoon
+item-snorkel = snorkel
+item-key = key
@@ -0,0 +1,214 @@
+# Messages en français.
+
+welcome = Vous êtes dans un monde bizarre rempli d'emojis étranges. Et qu'est-ce que je fais dans un terminal linux ?
+out-of-bounds = Restez avec nous, n'essayez pas de partir
+wandering = Vous vous baladez !
+troll-blocks-path = Il y a un troll ici ! Il vous faut une épée pour combattre !
+troll-defeated = Vous combattez bravement et terrassez le troll !
+troll-defeated-loot = Vous combattez bravement et terrassez le troll ! Il laisse tomber une { $item } par terre !
+spider-defeated = Vous avez vaincu l'araignée !
+spider-defeated-loot = Vous avez vaincu l'araignée et trouvé un { $item } sur son corps !
+fish-caught = Vous attrapez un poisson avec votre harpon ! En regardant dans le poisson, vous trouvez une { $item }... On dirait qu'il a mangé quelque chose de bizarre !
+fish-ignored = Vous n'avez plus envie de pêcher !
+fish-out-of-reach = Un poisson passe près de vous, mais vous n'avez rien pour l'attraper !
+swimming = Vous nagez comme un adorable petit poisson !
+water-blocked = Vous ne pouvez pas entrer dans l'eau comme ça, cherchez autour de vous quelque chose qui pourrait vous aider !
+tree-chopped = Vous avez abattu un arbre !
+chest-uncovered = Vous avez abattu un arbre... Et trouvé un coffre !
+tree-blocked = Il vous faudra un outil pour vous occuper d'un arbre !
+mountain-blocked = La montagne est trop escarpée pour être escaladée.
+chest-looted = Vous avez trouvé un { $item }, mais pour quoi faire ?
+chest-empty = Le coffre est vide. Vous avez déjà pris le { $item } qui s'y trouvait.
+castle-opened = Vous entrez dans le château et une magnifique princesse s'y trouve, vous avez trouvé l'amour et serez virtuellement heureux pour le reste de votre vie virtuelle. Appuyez sur Entrée pour continuer...
+castle-locked = La porte du château est verrouillée !
+trapped-in-web = C'est un piège !!! Vous êtes pris dans la toile d'araignée, l'araignée va bientôt revenir vous dévorer tout cru. Vous mourez. Appuyez sur Entrée pour continuer...
+unreachable = CE MESSAGE NE DEVRAIT JAMAIS APPARAÎTRE, CONTACTEZ LE DÉVELOPPEUR DU JEU !
+
+game-saved = Partie sauvegardée.
+save-failed = Impossible de sauvegarder la partie : { $error }
+
+hud-inventory = Inventaire du joueur :
+hud-time = Heure :
+phase-dawn = Aube
+phase-day = Jour
+phase-dusk = Crépuscule
+phase-night = Nuit
+hud-weather = Météo :
+weather-clear = Dégagé
+weather-rain = Pluie
+weather-fog = Brouillard
+weather-storm = Orage
+
+log-title = Historique des messages
+log-help = Haut/Bas/PgPréc/PgSuiv : défiler   / : rechercher   n/N : résultat suivant/précédent   Échap : fermer
+log-search-prompt = Rechercher :
+log-no-match = Aucun message ne contient « { $query } »
+
+help-title = Commandes
+help-close = Appuyez sur une touche pour revenir au jeu.
+action-move-north = Aller au nord
+action-move-south = Aller au sud
+action-move-west = Aller à l'ouest
+action-move-east = Aller à l'est
+action-move-north-west = Aller au nord-ouest
+action-move-north-east = Aller au nord-est
+action-move-south-west = Aller au sud-ouest
+action-move-south-east = Aller au sud-est
+action-wait = Attendre
+action-message-log = Historique des messages
+action-help = Commandes
+action-stats = Statistiques et succès
+action-toggle-panel = Afficher ou masquer le panneau d'aide
+action-save = Sauvegarder la partie
+action-quit = Quitter
+
+menu-title = Rust ASCII RPG
+menu-subtitle = Un monde bizarre rempli d'emojis étranges.
+menu-new-game = Nouvelle partie
+menu-continue = Continuer
+menu-load = Charger
+menu-settings = Paramètres
+menu-controls = Commandes
+menu-high-scores = Meilleurs scores
+menu-quit = Quitter
+menu-help = Haut/Bas : choisir   Entrée : valider   Échap : quitter
+menu-no-save = Il n'y a pas de partie sauvegardée à continuer.
+menu-load-prompt = Fichier à charger (sauvegarde .json ou carte) :
+menu-load-failed = Impossible de charger { $path } : { $error }
+
+settings-title = Paramètres
+settings-tileset = Jeu de tuiles
+settings-keybindings = Touches
+settings-difficulty = Difficulté
+settings-speedrun-timer = Chrono de speedrun
+settings-on = Activé
+settings-off = Désactivé
+settings-name = Nom du joueur
+settings-help = Haut/Bas : choisir   Gauche/Droite : changer   Entrée : saisir le nom   Échap : enregistrer et revenir
+settings-save-failed = Impossible d'enregistrer les paramètres : { $error }
+settings-invalid-keybindings = Touches invalides : { $error }
+settings-invalid-balance = Équilibrage invalide : { $error }
+tileset-emoji = Emoji
+tileset-ascii = ASCII
+preset-arrows = Flèches
+preset-wasd = WASD
+preset-vi = Touches vi
+difficulty-easy = Facile
+difficulty-normal = Normal
+difficulty-hard = Difficile
+
+end-victory = Victoire !
+end-defeat = Partie terminée
+end-seed = Graine : { $seed }
+end-turns = Tours joués : { $turns }
+end-items = Objets trouvés : { $items }
+end-kills = Créatures vaincues : { $kills }
+end-score = Score : { $score }
+end-high-score = Nouveau meilleur score : n° { $rank } sur cette graine !
+end-restart = Rejouer cette graine
+end-new-seed = Jouer une nouvelle graine
+end-main-menu = Retour au menu principal
+end-quit = Quitter
+end-help = Haut/Bas : choisir   Entrée : valider   Échap : menu principal
+
+panel-legend = Légende :
+panel-controls = Commandes :
+panel-hint = Astuce :
+panel-move = Se déplacer
+hint-spider-web = Attention, marcher dans une toile d'araignée est mortel.
+hint-troll = Un troll est juste à côté de vous, foncez dessus pour le combattre.
+hint-fish = Un poisson passe, il vous faudrait un harpon pour l'attraper.
+hint-castle = La porte du château est verrouillée, sa clé doit être cachée quelque part.
+hint-water = L'eau est profonde, il vous faut de quoi respirer dessous.
+hint-tree = Ces arbres pourraient être abattus avec le bon outil.
+hint-night = Les trolls chassent la nuit, tenez-vous à l'écart.
+hint-explore = Explorez le monde et trouvez un moyen d'entrer dans le château.
+tile-grass = herbe
+tile-tree = arbre
+tile-water = eau
+tile-mountain = montagne
+tile-sand = sable
+tile-castle = château
+tile-chest = coffre
+tile-spider-web = toile d'araignée
+tile-heart = cœur
+tile-shallow-water = eau peu profonde
+npc-fish = poisson
+npc-troll = troll
+npc-spider = araignée
+
+achievement-unlocked = Succès débloqué : { $name } !
+profile-save-failed = Impossible d'enregistrer le profil : { $error }
+stats-title = Statistiques
+stats-this-game = Cette partie
+stats-lifetime = Toutes
+stats-steps = Pas
+stats-tiles-swum = Cases nagées
+stats-trees-chopped = Arbres abattus
+stats-trolls-slain = Trolls terrassés
+stats-deaths-by-web = Morts dans une toile
+stats-games = Parties jouées
+stats-wins = Victoires
+stats-deaths = Morts
+stats-time-to-win = Tours pour gagner
+stats-achievements = Succès
+achievement-troll-slayer = Tueur de trolls
+achievement-troll-slayer-description = Terrasser 5 trolls en une seule partie.
+achievement-pacifist = Pacifiste
+achievement-pacifist-description = Gagner en ne terrassant que le seul troll nécessaire.
+achievement-lumberjack = Bûcheron
+achievement-lumberjack-description = Abattre 100 arbres en une seule partie.
+achievement-swimmer = Nageur
+achievement-swimmer-description = Nager 100 cases en une seule partie.
+achievement-speedrunner = Speedrunner
+achievement-speedrunner-description = Gagner en 500 tours ou moins.
+achievement-caught-in-the-web = Pris dans la toile
+achievement-caught-in-the-web-description = Mourir dans une toile d'araignée.
+achievement-veteran = Vétéran
+achievement-veteran-description = Gagner 10 parties.
+
+hud-timer = Chrono :
+timer-split = Étape : { $split } à { $time }
+timer-personal-best = Nouveau record personnel sur cette graine !
+timer-not-from-start = Le chrono ne tourne que pour les parties jouées depuis leur premier tour.
+splits-load-failed = Impossible de charger les meilleurs temps : { $error }
+splits-save-failed = Impossible d'enregistrer les meilleurs temps : { $error }
+split-first-troll = Premier troll
+split-axe = Hache
+split-chest = Coffre
+split-harpoon = Harpon
+split-key = Clé
+split-castle = Château
+
+name-title = Qui va là ?
+name-prompt = Votre nom :
+name-default = Aventurier
+
+scores-title = Meilleurs scores
+scores-table = Graine { $seed }, { $difficulty } ({ $page }/{ $pages })
+scores-won = gagné
+scores-died = mort
+scores-empty = Aucun score pour l'instant. Terminez une partie pour entrer au tableau.
+scores-help = Gauche/Droite : autres graines   Échap : retour
+scores-load-failed = Impossible de charger les meilleurs scores : { $error }
+scores-save-failed = Impossible d'enregistrer les meilleurs scores : { $error }
+replay-save-failed = Impossible d'enregistrer le replay : { $error }
+
+script-error = Erreur de script : { $error }
+
+dev-reloaded-balance = Équilibrage rechargé.
+dev-reloaded-content = Règles, scripts et mods rechargés.
+dev-reloaded-theme = Jeu de tuiles rechargé.
+dev-reload-failed = Échec du rechargement : { $error }
+
+bot-goal-snorkel = Bot : à la poursuite d'une araignée, pour son tuba.
+bot-goal-axe = Bot : à la poursuite d'un troll, pour sa hache.
+bot-goal-harpoon = Bot : abat des arbres pour trouver le coffre.
+bot-goal-key = Bot : pêche la clé.
+bot-goal-castle = Bot : en route vers le château.
+
+item-sword = épée
+item-axe = hache
+item-harpoon = harpon
+item-snorkel = tuba
+item-key = clé
@@ -0,0 +1,236 @@
+use crate::game::{self, Content};
+use crate::keybindings::Action;
+use crate::world::entities::{ItemType, NPCType};
+use crate::world::terrain::{TileType, TERRAIN_HEIGHT, TERRAIN_WIDTH};
+use crate::world::World;
+use std::collections::VecDeque;
+
+// A bot winning the game the way players do: it works out which items it
+// still needs, in the order they depend on each other, and walks to whatever
+// gives the next one. The plan is made again every turn, the NPCs and the
+// webs move.
+
+// Games the bot has not won after this many turns are given up
+pub const TURN_BUDGET: u32 = 3000;
+
+// What the bot goes for, each needs the ones before it
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Goal {
+    // beat a spider, first as the fish and the castle are mostly across deep
+    // water
+    Snorkel,
+    // beat a troll with the sword
+    Axe,
+    // chop trees until the chest shows up, then loot it
+    Harpoon,
+    // catch a fish with the harpoon
+    Key,
+    // open the castle with the key
+    Castle,
+}
+
+impl Goal {
+    pub fn message_id(self) -> &'static str {
+        match self {
+            Goal::Snorkel => "bot-goal-snorkel",
+            Goal::Axe => "bot-goal-axe",
+            Goal::Harpoon => "bot-goal-harpoon",
+            Goal::Key => "bot-goal-key",
+            Goal::Castle => "bot-goal-castle",
+        }
+    }
+
+    // Whether every tile is where the goal is reached, by moving onto it
+    fn targets(self, world: &World) -> Vec<bool> {
+        let tiles_like = |tile_type: TileType| -> Vec<bool> {
+            let like: Vec<bool> = TileType::all()
+                .map(|tile| tile.like() == tile_type)
+                .collect();
+            world
+                .terrain
+                .tiles
+                .iter()
+                .map(|tile| like[tile.index()])
+                .collect()
+        };
+        // creatures are met on the tiles they live on, not on their webs
+        let npcs_like = |npc_type: NPCType| {
+            let mut targets = vec![false; world.terrain.tiles.len()];
+            for npc in &world.npcs {
+                let index = npc.y as usize * TERRAIN_WIDTH + npc.x as usize;
+                if npc.npc_type.like() == npc_type
+                    && world.terrain.tiles[index] == npc.npc_type.allowed_tile()
+                {
+                    targets[index] = true;
+                }
+            }
+            targets
+        };
+        match self {
+            Goal::Snorkel => npcs_like(NPCType::SPIDER),
+            Goal::Axe => npcs_like(NPCType::TROLL),
+            Goal::Harpoon => {
+                // once uncovered the chest is all that counts
+                let chests = tiles_like(TileType::CHEST);
+                if chests.contains(&true) {
+                    chests
+                } else {
+                    tiles_like(TileType::TREE)
+                }
+            }
+            Goal::Key => npcs_like(NPCType::FISH),
+            Goal::Castle => tiles_like(TileType::CASTLE),
+        }
+    }
+}
+
+// The goals left, in the order the bot goes for them
+pub fn plan(world: &World) -> Vec<Goal> {
+    let player = &world.player;
+    let mut goals = Vec::new();
+    if !player.has(ItemType::SNORKEL) && !player.has(ItemType::KEY) {
+        goals.push(Goal::Snorkel);
+    }
+    if !player.has(ItemType::AXE) {
+        goals.push(Goal::Axe);
+    }
+    if !player.has(ItemType::HARPOON) {
+        goals.push(Goal::Harpoon);
+    }
+    if !player.has(ItemType::KEY) {
+        goals.push(Goal::Key);
+    }
+    goals.push(Goal::Castle);
+    goals
+}
+
+// The move towards the goal the bot is going for, waiting when there is no
+// way to it yet
+pub fn next_action(world: &World) -> Action {
+    let swims = world.player.has(ItemType::SNORKEL);
+    step_towards(world, current_goal(world), swims).unwrap_or(Action::Wait)
+}
+
+// The first goal of the plan, but the snorkel is left for later while no
+// spider can be reached
+pub fn current_goal(world: &World) -> Goal {
+    let goals = plan(world);
+    if goals[0] == Goal::Snorkel && step_towards(world, Goal::Snorkel, false).is_none() {
+        goals[1]
+    } else {
+        goals[0]
+    }
+}
+
+// Play the world to its end, or until the turn budget runs out. Returns
+// whether the bot won.
+pub fn play(world: &mut World, content: &Content) -> bool {
+    while !world.player.dead && world.stats.won_at.is_none() && world.turn < TURN_BUDGET {
+        let action = next_action(world);
+        game::play_turn(world, content, action);
+    }
+    world.stats.won_at.is_some()
+}
+
+// The first step of a shortest walk to a target of the goal, the last step
+// being onto the target
+fn step_towards(world: &World, goal: Goal, swims: bool) -> Option<Action> {
+    let walkable: Vec<bool> = TileType::all()
+        .map(|tile| match tile.like() {
+            TileType::GRASS | TileType::SAND | TileType::SHALLOW_WATER => true,
+            TileType::WATER => swims,
+            // chopped on the way
+            TileType::TREE => world.player.has(ItemType::AXE),
+            _ => false,
+        })
+        .collect();
+    let targets = goal.targets(world);
+    let start = (world.player.x, world.player.y);
+    // the first step taken to reach every tile reached
+    let mut first_steps = vec![None; TERRAIN_WIDTH * TERRAIN_HEIGHT];
+    let mut pending = VecDeque::from([start]);
+    while let Some((x, y)) = pending.pop_front() {
+        for action in &MOVES {
+            let (dx, dy) = action.direction().unwrap();
+            let (next_x, next_y) = (x + dx, y + dy);
+            if next_x < 0
+                || next_y < 0
+                || next_x >= TERRAIN_WIDTH as i32
+                || next_y >= TERRAIN_HEIGHT as i32
+                || (next_x, next_y) == start
+            {
+                continue;
+            }
+            let index = next_y as usize * TERRAIN_WIDTH + next_x as usize;
+            if first_steps[index].is_some() {
+                continue;
+            }
+            let first_step = if (x, y) == start {
+                *action
+            } else {
+                first_steps[y as usize * TERRAIN_WIDTH + x as usize].unwrap()
+            };
+            if targets[index] {
+                return Some(first_step);
+            }
+            if walkable[world.terrain.tiles[index].index()] {
+                first_steps[index] = Some(first_step);
+                pending.push_back((next_x, next_y));
+            }
+        }
+    }
+    None
+}
+
+const MOVES: [Action; 8] = [
+    Action::MoveNorth,
+    Action::MoveSouth,
+    Action::MoveWest,
+    Action::MoveEast,
+    Action::MoveNorthWest,
+    Action::MoveNorthEast,
+    Action::MoveSouthWest,
+    Action::MoveSouthEast,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Balance;
+
+    #[test]
+    fn plans_follow_the_items() {
+        let mut world = World::with_seed(11, Balance::default());
+        assert_eq!(plan(&world)[..2], [Goal::Snorkel, Goal::Axe]);
+        assert_eq!(*plan(&world).last().unwrap(), Goal::Castle);
+        world.give_item(ItemType::AXE);
+        world.give_item(ItemType::HARPOON);
+        assert_eq!(plan(&world), [Goal::Snorkel, Goal::Key, Goal::Castle]);
+        world.give_item(ItemType::KEY);
+        assert_eq!(plan(&world), [Goal::Castle]);
+    }
+
+    // Seeds the bot loses out of `seeds`
+    fn lost(seeds: std::ops::Range<u64>) -> Vec<u64> {
+        let content = Content::default();
+        seeds
+            .filter(|&seed| !play(&mut World::with_seed(seed, Balance::default()), &content))
+            .collect()
+    }
+
+    // The player and the creatures spawn on the mainland, and the spiders
+    // never weave it apart, so every world can be won
+    #[test]
+    fn seeds_are_winnable() {
+        let lost = lost(0..200);
+        assert!(lost.is_empty(), "the bot lost seeds {:?}", lost);
+    }
+
+    // cargo test --release -- --ignored
+    #[test]
+    #[ignore]
+    fn thousands_of_seeds_are_winnable() {
+        let lost = lost(0..5000);
+        assert!(lost.is_empty(), "the bot lost seeds {:?}", lost);
+    }
+}
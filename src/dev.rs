@@ -0,0 +1,270 @@
+use crate::game::Content;
+use crate::i18n::{Catalog, Language};
+use crate::menu::ConfigFiles;
+use crate::settings::{Settings, SETTINGS_FILE};
+use crate::tileset::Tileset;
+use crate::world::{Balance, Difficulty};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use termion::event::Key;
+use termion::input::TermRead;
+
+// How often --dev looks at the files for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// What --dev reloads, and the files it comes from
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Change {
+    Balance,
+    // the rules, the scripts and the mods, with their types and messages
+    Content,
+    // the tileset of the settings
+    Theme,
+}
+
+// Something reloaded, for the running game or the menu to use instead
+pub enum Reloaded {
+    Balance(Balance),
+    Content(Box<Content>, Catalog),
+    Theme(Tileset),
+}
+
+impl Reloaded {
+    pub fn message_id(&self) -> &'static str {
+        match self {
+            Reloaded::Balance(_) => "dev-reloaded-balance",
+            Reloaded::Content(..) => "dev-reloaded-content",
+            Reloaded::Theme(_) => "dev-reloaded-theme",
+        }
+    }
+}
+
+// When every file was last modified, missing ones left out
+type Stamps = Vec<(PathBuf, SystemTime)>;
+
+// Notices the watched files changing, by polling their modification times
+pub struct Watch {
+    watched: Vec<(Change, Vec<PathBuf>, Stamps)>,
+}
+
+impl Watch {
+    pub fn new(files: &ConfigFiles) -> Watch {
+        let watched = [
+            (Change::Balance, vec![files.balance.clone()]),
+            (
+                Change::Content,
+                vec![
+                    files.rules.clone(),
+                    files.scripts.clone(),
+                    files.mods.clone(),
+                ],
+            ),
+            (Change::Theme, vec![PathBuf::from(SETTINGS_FILE)]),
+        ];
+        Watch {
+            watched: watched
+                .into_iter()
+                .map(|(change, paths)| {
+                    let stamps = stamps(&paths);
+                    (change, paths, stamps)
+                })
+                .collect(),
+        }
+    }
+
+    // What changed since the last call
+    fn changes(&mut self) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for (change, paths, old) in &mut self.watched {
+            let new = stamps(paths);
+            if new != *old {
+                *old = new;
+                changes.push(*change);
+            }
+        }
+        changes
+    }
+
+    // Load again what changed since the last call. Errors are for the player
+    // to read, the game goes on with what it had.
+    pub fn reload(
+        &mut self,
+        files: &ConfigFiles,
+        difficulty: Difficulty,
+        language: Language,
+    ) -> Vec<Result<Reloaded, String>> {
+        self.changes()
+            .into_iter()
+            .map(|change| match change {
+                Change::Balance => Balance::load(&files.balance, difficulty).map(Reloaded::Balance),
+                Change::Content => {
+                    let mut catalog = Catalog::new(language);
+                    let content = Content::load(files, &mut catalog)?;
+                    Ok(Reloaded::Content(Box::new(content), catalog))
+                }
+                Change::Theme => Settings::load(Path::new(SETTINGS_FILE))
+                    .map(|settings| Reloaded::Theme(settings.tileset)),
+            })
+            .collect()
+    }
+}
+
+// The files under the paths, directories included
+fn stamps(paths: &[PathBuf]) -> Stamps {
+    files(paths)
+        .into_iter()
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|metadata| metadata.modified());
+            modified.ok().map(|modified| (path, modified))
+        })
+        .collect()
+}
+
+// The files at the paths and in the directories under them, sorted
+pub fn files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = paths.to_vec();
+    while let Some(path) = pending.pop() {
+        if path.is_dir() {
+            if let Ok(entries) = fs::read_dir(&path) {
+                pending.extend(entries.filter_map(|entry| entry.ok().map(|entry| entry.path())));
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+// What the menu and the game wait for
+pub enum Input {
+    Key(Key),
+    // with --dev, a watched file changed
+    Reload,
+}
+
+// The keys pressed, as the input of the menu and the game without --dev
+pub fn inputs<I: Iterator<Item = Result<Key, io::Error>>>(
+    keys: I,
+) -> impl Iterator<Item = Result<Input, io::Error>> {
+    keys.map(|key| key.map(Input::Key))
+}
+
+// The keys pressed, with an Input::Reload whenever a watched file changes,
+// which wakes up the menu or the game waiting for a key to reload it
+pub fn keys(files: &ConfigFiles) -> mpsc::IntoIter<Result<Input, io::Error>> {
+    let (sender, receiver) = mpsc::channel();
+
+    let keys = sender.clone();
+    thread::spawn(move || {
+        for key in io::stdin().keys() {
+            if keys.send(key.map(Input::Key)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut watch = Watch::new(files);
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        if !watch.changes().is_empty() && sender.send(Ok(Input::Reload)).is_err() {
+            break;
+        }
+    });
+
+    receiver.into_iter()
+}
+
+// Only the keys of the input, for the screens that do not reload anything.
+// Files changing while they are shown are reloaded on the next change.
+pub struct KeysOnly<'a, I>(pub &'a mut I);
+
+impl<I: Iterator<Item = Result<Input, io::Error>>> Iterator for KeysOnly<'_, I> {
+    type Item = Result<Key, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.0.next()? {
+                Ok(Input::Key(key)) => return Some(Ok(key)),
+                Ok(Input::Reload) => {}
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Config files in a fresh temporary directory, none of them there yet
+    fn config_files(name: &str) -> ConfigFiles {
+        let dir = std::env::temp_dir().join(format!("game-dev-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        ConfigFiles {
+            keybindings: dir.join("keybindings.toml"),
+            balance: dir.join("balance.toml"),
+            rules: dir.join("rules.toml"),
+            scripts: dir.join("scripts"),
+            mods: dir.join("mods"),
+            dev: true,
+        }
+    }
+
+    fn reload(watch: &mut Watch, files: &ConfigFiles) -> Vec<Result<Reloaded, String>> {
+        watch.reload(files, Difficulty::Normal, Language::English)
+    }
+
+    #[test]
+    fn nothing_is_reloaded_when_nothing_changed() {
+        let files = config_files("unchanged");
+        let mut watch = Watch::new(&files);
+        assert!(reload(&mut watch, &files).is_empty());
+    }
+
+    #[test]
+    fn broken_balance_is_reported() {
+        let files = config_files("balance");
+        let mut watch = Watch::new(&files);
+        fs::write(&files.balance, "player_health = [").unwrap();
+        let results = reload(&mut watch, &files);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].as_ref().err().unwrap().contains("balance.toml"));
+        // reported once, not again until the file changes
+        assert!(reload(&mut watch, &files).is_empty());
+    }
+
+    #[test]
+    fn broken_scripts_are_reported_then_reloaded_once_fixed() {
+        let files = config_files("scripts");
+        let mut watch = Watch::new(&files);
+        fs::create_dir_all(&files.scripts).unwrap();
+        fs::write(files.scripts.join("broken.rhai"), "fn on_turn(turn) {").unwrap();
+        let results = reload(&mut watch, &files);
+        assert_eq!(results.len(), 1);
+        let error = results[0].as_ref().err().unwrap();
+        assert!(error.starts_with("scripts:") && error.contains("broken.rhai"));
+
+        fs::remove_file(files.scripts.join("broken.rhai")).unwrap();
+        fs::write(files.scripts.join("fixed.rhai"), "fn on_turn(turn) {}").unwrap();
+        let results = reload(&mut watch, &files);
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0], Ok(Reloaded::Content(..))));
+    }
+
+    #[test]
+    fn broken_rules_are_reported() {
+        let files = config_files("rules");
+        let mut watch = Watch::new(&files);
+        fs::write(&files.rules, "[[rule]]\ntile = 42").unwrap();
+        let results = reload(&mut watch, &files);
+        assert_eq!(results.len(), 1);
+        assert!(results[0].as_ref().err().unwrap().starts_with("rules:"));
+    }
+}
@@ -0,0 +1,383 @@
+use crate::world::entities::*;
+use crate::world::terrain::*;
+use crate::world::{Map, World};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use termion::{clear, cursor, event::Key, input::TermRead};
+
+// How many edits can be undone
+const UNDO_LIMIT: usize = 100;
+
+// Tiles selectable with the number keys at a time, Tab goes to the others
+const PALETTE_PAGE: usize = 9;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Tool {
+    Brush,
+    Fill,
+}
+
+struct Editor {
+    map: Map,
+    path: PathBuf,
+    cursor_x: i32,
+    cursor_y: i32,
+    palette_index: usize,
+    tool: Tool,
+    undo_stack: Vec<Map>,
+    redo_stack: Vec<Map>,
+    unsaved_changes: bool,
+}
+
+// Map editor: paint the terrain, place the player start and NPC spawns, and save the map file.
+// A missing map file starts the editor on a freshly generated world.
+pub fn start<W: Write>(stdout: &mut W, path: &Path) -> Result<(), io::Error> {
+    let stdin = io::stdin();
+    let mut keys = stdin.keys();
+
+    let map = if path.exists() {
+        Map::load(path)?
+    } else {
+        Map::from_world(&World::new())
+    };
+    let mut editor = Editor::new(map, path);
+    let mut status_message = format!("Editing {}", path.display());
+    let mut quit_requested = false;
+    editor.render(stdout, &status_message)?;
+
+    for key_event in keys.by_ref() {
+        let key_event = key_event?;
+        status_message = match key_event {
+            Key::Up => editor.move_cursor(0, -1),
+            Key::Down => editor.move_cursor(0, 1),
+            Key::Left => editor.move_cursor(-1, 0),
+            Key::Right => editor.move_cursor(1, 0),
+            Key::Char(c @ '1'..='9') => editor.select_slot(c as usize - '1' as usize),
+            Key::Char('\t') => editor.cycle_tile(1),
+            Key::BackTab => editor.cycle_tile(-1),
+            Key::Char('b') => {
+                editor.tool = Tool::Brush;
+                "Brush tool".to_string()
+            }
+            Key::Char('f') => {
+                editor.tool = Tool::Fill;
+                "Flood fill tool".to_string()
+            }
+            Key::Char(' ') => editor.apply_tool(),
+            Key::Char('p') => editor.place_player_start(),
+            Key::Char('F') => editor.place_npc(NPCType::FISH),
+            Key::Char('T') => editor.place_npc(NPCType::TROLL),
+            Key::Char('S') => editor.place_npc(NPCType::SPIDER),
+            Key::Char('x') => editor.remove_npc(),
+            Key::Char('u') | Key::Ctrl('z') => editor.undo(),
+            Key::Char('r') | Key::Ctrl('y') => editor.redo(),
+            Key::Char('w') | Key::Ctrl('s') => editor.save(),
+            Key::Esc => {
+                if !editor.unsaved_changes || quit_requested {
+                    break;
+                }
+                quit_requested = true;
+                editor.render(
+                    stdout,
+                    "Unsaved changes! Press Escape again to quit anyway.",
+                )?;
+                continue;
+            }
+            _ => continue,
+        };
+        quit_requested = false;
+        editor.render(stdout, &status_message)?;
+    }
+
+    Ok(())
+}
+
+impl Editor {
+    fn new(map: Map, path: &Path) -> Editor {
+        Editor {
+            cursor_x: map.player_start.0,
+            cursor_y: map.player_start.1,
+            map,
+            path: path.to_path_buf(),
+            palette_index: 0,
+            tool: Tool::Brush,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            unsaved_changes: false,
+        }
+    }
+
+    fn cursor_index(&self) -> usize {
+        self.cursor_y as usize * TERRAIN_WIDTH + self.cursor_x as usize
+    }
+
+    fn selected_tile(&self) -> TileType {
+        TileType::all()
+            .nth(self.palette_index)
+            .unwrap_or(TileType::GRASS)
+    }
+
+    fn move_cursor(&mut self, dx: i32, dy: i32) -> String {
+        self.cursor_x = (self.cursor_x + dx).clamp(0, TERRAIN_WIDTH as i32 - 1);
+        self.cursor_y = (self.cursor_y + dy).clamp(0, TERRAIN_HEIGHT as i32 - 1);
+        format!(
+            "({}, {}) {}",
+            self.cursor_x,
+            self.cursor_y,
+            self.map.terrain.tiles[self.cursor_index()].name()
+        )
+    }
+
+    // The number keys pick a tile of the page the selected tile is on
+    fn select_slot(&mut self, slot: usize) -> String {
+        let palette_index = self.palette_index / PALETTE_PAGE * PALETTE_PAGE + slot;
+        if palette_index >= TileType::all().count() {
+            return "No tile in this palette slot".to_string();
+        }
+        self.palette_index = palette_index;
+        format!("Selected {}", self.selected_tile().name())
+    }
+
+    // Select the next or the previous tile, going round the whole palette
+    fn cycle_tile(&mut self, step: isize) -> String {
+        let count = TileType::all().count() as isize;
+        self.palette_index = (self.palette_index as isize + step).rem_euclid(count) as usize;
+        format!("Selected {}", self.selected_tile().name())
+    }
+
+    // Save the current map so the next edit can be undone
+    fn checkpoint(&mut self) {
+        self.undo_stack.push(self.map.clone());
+        if self.undo_stack.len() > UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+        self.unsaved_changes = true;
+    }
+
+    fn apply_tool(&mut self) -> String {
+        let tile_type = self.selected_tile();
+        let index = self.cursor_index();
+        if self.map.terrain.tiles[index] == tile_type {
+            return format!("Already {}", tile_type.name());
+        }
+        self.checkpoint();
+        match self.tool {
+            Tool::Brush => {
+                self.map.terrain.tiles[index] = tile_type;
+                format!("Painted {}", tile_type.name())
+            }
+            Tool::Fill => {
+                let filled = self.map.terrain.flood_fill(
+                    self.cursor_x as usize,
+                    self.cursor_y as usize,
+                    tile_type,
+                );
+                format!("Filled {} tiles with {}", filled, tile_type.name())
+            }
+        }
+    }
+
+    fn place_player_start(&mut self) -> String {
+        self.checkpoint();
+        self.map.player_start = (self.cursor_x, self.cursor_y);
+        self.map
+            .npcs
+            .retain(|npc| (npc.x, npc.y) != self.map.player_start);
+        "Player start placed".to_string()
+    }
+
+    fn place_npc(&mut self, npc_type: NPCType) -> String {
+        let allowed_tile = npc_type.allowed_tile();
+        if self.map.terrain.tiles[self.cursor_index()] != allowed_tile {
+            return format!(
+                "A {} can only spawn on {}",
+                npc_type.id(),
+                allowed_tile.name()
+            );
+        }
+        if (self.cursor_x, self.cursor_y) == self.map.player_start {
+            return "The player starts here".to_string();
+        }
+        self.checkpoint();
+        self.map
+            .npcs
+            .retain(|npc| (npc.x, npc.y) != (self.cursor_x, self.cursor_y));
+        self.map.npcs.push(NPC {
+            x: self.cursor_x,
+            y: self.cursor_y,
+            npc_type,
+            energy: 0,
+        });
+        format!("Placed a {} spawn", npc_type.id())
+    }
+
+    fn remove_npc(&mut self) -> String {
+        let position = (self.cursor_x, self.cursor_y);
+        if !self.map.npcs.iter().any(|npc| (npc.x, npc.y) == position) {
+            return "No spawn here".to_string();
+        }
+        self.checkpoint();
+        self.map.npcs.retain(|npc| (npc.x, npc.y) != position);
+        "Spawn removed".to_string()
+    }
+
+    fn undo(&mut self) -> String {
+        match self.undo_stack.pop() {
+            Some(previous) => {
+                self.redo_stack
+                    .push(std::mem::replace(&mut self.map, previous));
+                self.unsaved_changes = true;
+                "Undone".to_string()
+            }
+            None => "Nothing to undo".to_string(),
+        }
+    }
+
+    fn redo(&mut self) -> String {
+        match self.redo_stack.pop() {
+            Some(next) => {
+                self.undo_stack.push(std::mem::replace(&mut self.map, next));
+                self.unsaved_changes = true;
+                "Redone".to_string()
+            }
+            None => "Nothing to redo".to_string(),
+        }
+    }
+
+    fn save(&mut self) -> String {
+        if let Err(message) = self.map.validate() {
+            return format!("Cannot save: {}", message);
+        }
+        match self.map.save(&self.path) {
+            Ok(()) => {
+                self.unsaved_changes = false;
+                format!("Saved {}", self.path.display())
+            }
+            Err(error) => format!("Cannot save: {}", error),
+        }
+    }
+
+    fn render<W: Write>(&self, stdout: &mut W, status_message: &str) -> Result<(), io::Error> {
+        let help = "Arrows: move  1-9/Tab/Shift-Tab: tile  b/f: brush/fill  Space: apply  p: player start\r\n\
+                    F/T/S: fish/troll/spider spawn  x: remove spawn  u/r: undo/redo  w: save  Esc: quit";
+        let mut frame = format!("{}\r\n\r\n", help);
+
+        for y in 0..TERRAIN_HEIGHT as i32 {
+            for x in 0..TERRAIN_WIDTH as i32 {
+                if (x, y) == (self.cursor_x, self.cursor_y) {
+                    frame.push('🔲');
+                } else if (x, y) == self.map.player_start {
+                    frame.push('🏃');
+                } else if let Some(npc) = self.map.npcs.iter().find(|npc| (npc.x, npc.y) == (x, y))
+                {
+                    frame.push_str(npc.npc_type.symbol());
+                } else {
+                    let index = y as usize * TERRAIN_WIDTH + x as usize;
+                    frame.push_str(self.map.terrain.tiles[index].symbol());
+                }
+            }
+            frame.push_str("\r\n");
+        }
+
+        // the page of the palette with the selected tile, highlighted
+        let pages = TileType::all().count().div_ceil(PALETTE_PAGE);
+        let page = self.palette_index / PALETTE_PAGE;
+        let mut palette = String::from("\x1b[1m\x1b[93mPalette:\x1b[0m ");
+        if pages > 1 {
+            palette.push_str(&format!("({}/{}) ", page + 1, pages));
+        }
+        let tiles = TileType::all().enumerate().skip(page * PALETTE_PAGE);
+        for (slot, (i, tile_type)) in tiles.take(PALETTE_PAGE).enumerate() {
+            if i == self.palette_index {
+                palette.push_str(&format!(
+                    "\x1b[7m{}{}\x1b[0m ",
+                    slot + 1,
+                    tile_type.symbol()
+                ));
+            } else {
+                palette.push_str(&format!("{}{} ", slot + 1, tile_type.symbol()));
+            }
+        }
+        let tool = match self.tool {
+            Tool::Brush => "brush",
+            Tool::Fill => "fill",
+        };
+        frame.push_str(&format!("\r\n{} \x1b[1mTool:\x1b[0m {}\r\n", palette, tool));
+        frame.push_str(&format!("\r\n{}\r\n", status_message));
+
+        write!(stdout, "{}{}{}", clear::All, cursor::Goto(1, 1), frame)?;
+        stdout.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Balance;
+
+    fn editor() -> Editor {
+        let map = Map::from_world(&World::with_seed(3, Balance::default()));
+        Editor::new(map, Path::new("test.map"))
+    }
+
+    #[test]
+    fn edits_are_undone_and_redone() {
+        let mut editor = editor();
+        let original = editor.map.serialize();
+        editor.select_slot(TileType::MOUNTAIN.index());
+        editor.apply_tool();
+        let painted = editor.map.serialize();
+        assert_ne!(painted, original);
+
+        editor.undo();
+        assert_eq!(editor.map.serialize(), original);
+        editor.redo();
+        assert_eq!(editor.map.serialize(), painted);
+        assert_eq!(editor.redo(), "Nothing to redo");
+
+        // a new edit drops what could be redone
+        editor.undo();
+        editor.move_cursor(1, 0);
+        editor.apply_tool();
+        assert_eq!(editor.redo(), "Nothing to redo");
+        editor.undo();
+        assert_eq!(editor.map.serialize(), original);
+        assert_eq!(editor.undo(), "Nothing to undo");
+    }
+
+    #[test]
+    fn undo_history_is_bounded() {
+        let mut editor = editor();
+        for i in 0..UNDO_LIMIT + 20 {
+            editor.cycle_tile(1);
+            editor.move_cursor(i as i32 % 2 * 2 - 1, 0);
+            editor.apply_tool();
+        }
+        assert_eq!(editor.undo_stack.len(), UNDO_LIMIT);
+    }
+
+    #[test]
+    fn every_tile_can_be_selected() {
+        let mut editor = editor();
+        let count = TileType::all().count();
+        assert!(count > PALETTE_PAGE);
+        for i in 0..count {
+            assert!(editor.selected_tile() == TileType::all().nth(i).unwrap());
+            editor.cycle_tile(1);
+        }
+        assert!(editor.selected_tile() == TileType::GRASS);
+
+        // the number keys pick on the page of the selected tile
+        editor.cycle_tile(-1);
+        assert!(editor.selected_tile() == TileType::SHALLOW_WATER);
+        editor.select_slot(0);
+        assert!(editor.selected_tile() == TileType::SHALLOW_WATER);
+        assert_eq!(editor.select_slot(8), "No tile in this palette slot");
+        editor.cycle_tile(1);
+        editor.select_slot(1);
+        assert!(editor.selected_tile() == TileType::TREE);
+    }
+}
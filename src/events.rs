@@ -0,0 +1,87 @@
+use crate::world::entities::{ItemType, NPCType};
+use crate::world::terrain::TileType;
+
+// Something that happened in the game world. The core only records events,
+// whoever is interested subscribes to the EventBus to react to them.
+#[derive(Clone)]
+pub enum GameEvent {
+    PlayerMoved {
+        from: (i32, i32),
+        to: (i32, i32),
+    },
+    NpcKilled {
+        npc_type: NPCType,
+        x: i32,
+        y: i32,
+    },
+    ItemAcquired(ItemType),
+    TileChanged {
+        x: i32,
+        y: i32,
+        from: TileType,
+        to: TileType,
+    },
+    PlayerDied,
+    GameWon,
+}
+
+type Handler<C> = Box<dyn FnMut(&mut C, &GameEvent)>;
+
+// Handlers get the event and the context `C` it is published with, whatever
+// they keep up to date.
+pub struct EventBus<C> {
+    handlers: Vec<Handler<C>>,
+}
+
+impl<C> Default for EventBus<C> {
+    fn default() -> Self {
+        EventBus {
+            handlers: Vec::new(),
+        }
+    }
+}
+
+impl<C> EventBus<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Register a handler, it is called for every published event in subscription order.
+    pub fn subscribe<F: FnMut(&mut C, &GameEvent) + 'static>(&mut self, handler: F) {
+        self.handlers.push(Box::new(handler));
+    }
+
+    pub fn publish(&mut self, context: &mut C, event: &GameEvent) {
+        for handler in &mut self.handlers {
+            handler(context, event);
+        }
+    }
+
+    pub fn publish_all(&mut self, context: &mut C, events: &[GameEvent]) {
+        for event in events {
+            self.publish(context, event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handlers_run_in_subscription_order() {
+        let mut bus = EventBus::new();
+        bus.subscribe(|seen: &mut Vec<String>, event| {
+            if let GameEvent::ItemAcquired(item) = event {
+                seen.push(format!("first {}", item.id()));
+            }
+        });
+        bus.subscribe(|seen: &mut Vec<String>, _| seen.push("second".to_string()));
+        let mut seen = Vec::new();
+        bus.publish_all(
+            &mut seen,
+            &[GameEvent::ItemAcquired(ItemType::AXE), GameEvent::GameWon],
+        );
+        assert_eq!(seen, ["first axe", "second", "second"]);
+    }
+}
@@ -1,63 +1,501 @@
+use crate::bot;
+use crate::dev::{self, Input, KeysOnly, Reloaded, Watch};
+use crate::events::{EventBus, GameEvent};
+use crate::i18n::Catalog;
+use crate::keybindings::{self, Action, KeyBindings};
+use crate::menu::ConfigFiles;
+use crate::message_log::{self, Category, MessageLog, MESSAGE_LOG_CAPACITY};
+use crate::mods::Mods;
+use crate::outcome::Outcome;
+use crate::panel;
+use crate::profile::{self, Profile};
+use crate::scheduler::{self, ACTION_COST};
+use crate::scripting::{self, Scripts};
+use crate::settings::Settings;
+use crate::speedrun::{format_time, PersonalBests, Timer, SPLITS_FILE};
+use crate::tileset::Tileset;
 use crate::world::entities::*;
+use crate::world::save::SAVE_FILE;
 use crate::world::terrain::*;
-use crate::world::World;
+use crate::world::{weather, DayPhase, Difficulty, Rules, RunStats, World};
 use rand::seq::SliceRandom;
 use rand::Rng;
+use std::fs;
 use std::io::{self, Write};
+use std::path::Path;
 use std::thread;
 use std::time::Duration;
-use termion::terminal_size;
-use termion::{clear, cursor, event::Key, input::TermRead};
+use termion::{clear, cursor, event::Key};
 
-pub fn start<W: Write>(stdout: &mut W) -> Result<(), io::Error> {
-    let stdin = io::stdin();
-    let mut keys = stdin.keys();
+// How many of the latest messages are shown under the map
+const HUD_LOG_LINES: usize = 5;
+// How close the player must be for a troll to chase them at night
+const TROLL_HUNT_RADIUS: i32 = 10;
+// How long a turn of the bot stays on screen when watching it play
+const BOT_TURN_DELAY: Duration = Duration::from_millis(100);
 
-    let mut world = World::new();
-    let mut status_message = String::from( "You are in a bizarre world full of weird emojis. And what am I doing inside a linux terminal?");
-    render(&world, stdout, &status_message)?;
+// How a game ended, shown on the end screen
+#[derive(PartialEq)]
+pub struct Summary {
+    pub won: bool,
+    pub name: String,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub turns: u32,
+    pub items: Vec<ItemType>,
+    pub stats: RunStats,
+    // every action that took a turn, to replay the game
+    pub actions: Vec<Action>,
+}
+
+impl Summary {
+    pub fn new(world: World, actions: Vec<Action>) -> Summary {
+        Summary {
+            won: world.stats.won_at.is_some(),
+            name: world.player.name,
+            seed: world.seed,
+            difficulty: world.balance.difficulty,
+            turns: world.turn,
+            items: world.player.inventory,
+            stats: world.stats,
+            actions,
+        }
+    }
+}
+
+// What the game is made of, besides the code. By default the built-in rules,
+// without scripts or mods.
+#[derive(Default)]
+pub struct Content {
+    pub rules: Rules,
+    pub scripts: Scripts,
+    // "<pack>: ..." for every type a mod replaced
+    pub conflicts: Vec<String>,
+    // hash of the files it was loaded from, 0 for the built-in content, so a
+    // replay can tell whether it is played with what it was recorded with
+    pub fingerprint: u64,
+}
+
+impl Content {
+    // Load the mods, then the rules and the scripts on top of theirs. The
+    // messages of the mods go to the catalog.
+    pub fn load(files: &ConfigFiles, catalog: &mut Catalog) -> Result<Content, String> {
+        let mods = Mods::load(&files.mods).map_err(|message| format!("mods: {}", message))?;
+        for (language, source) in &mods.locales {
+            catalog.add(*language, source);
+        }
+        let rules = Rules::load(&files.rules, mods.rules)
+            .map_err(|message| format!("rules: {}", message))?;
+        let mut scripts =
+            Scripts::load(&files.scripts).map_err(|message| format!("scripts: {}", message))?;
+        for dir in &mods.scripts {
+            scripts
+                .add(dir)
+                .map_err(|message| format!("scripts: {}", message))?;
+        }
+        Ok(Content {
+            rules,
+            scripts,
+            conflicts: mods.conflicts,
+            fingerprint: fingerprint(&[
+                files.rules.clone(),
+                files.scripts.clone(),
+                files.mods.clone(),
+            ]),
+        })
+    }
+}
+
+// FNV-1a of the names and contents of the files, which unlike the std hasher
+// stays the same from one build to the next
+fn fingerprint(paths: &[std::path::PathBuf]) -> u64 {
+    let files = dev::files(paths);
+    if files.is_empty() {
+        return 0;
+    }
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for path in files {
+        let name = path.to_string_lossy().into_owned().into_bytes();
+        for byte in name.into_iter().chain(fs::read(&path).unwrap_or_default()) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+// What a game is played with, besides the world
+pub struct Setup<'a> {
+    pub settings: &'a Settings,
+    pub keybindings: &'a KeyBindings,
+    pub content: &'a Content,
+    pub catalog: &'a Catalog,
+    pub files: &'a ConfigFiles,
+}
+
+// What the game screen shows around the world
+struct View<'a> {
+    log: MessageLog,
+    tileset: Tileset,
+    keybindings: &'a KeyBindings,
+    show_panel: bool,
+    // only runs when enabled in the settings, for games played from their first turn
+    timer: Option<Timer>,
+}
+
+// Play the world until the player quits, dies or wins. Returns the summary of
+// the game when it is over, nothing when the player quit.
+pub fn start<W: Write, I: Iterator<Item = Result<Input, io::Error>>>(
+    stdout: &mut W,
+    keys: &mut I,
+    mut world: World,
+    setup: &Setup,
+    profile: &mut Profile,
+) -> Result<Option<Summary>, io::Error> {
+    let &Setup {
+        settings,
+        keybindings,
+        catalog,
+        files,
+        ..
+    } = setup;
+    let mut view = View {
+        log: MessageLog::new(MESSAGE_LOG_CAPACITY),
+        tileset: settings.tileset,
+        keybindings,
+        show_panel: true,
+        timer: None,
+    };
+    view.log.push(
+        world.turn,
+        Outcome::Welcome.category(),
+        Outcome::Welcome.message(catalog),
+    );
+
+    let mut bests = PersonalBests::default();
+    if settings.speedrun_timer {
+        if world.turn > 0 {
+            view.log.push(
+                world.turn,
+                Category::Info,
+                catalog.message("timer-not-from-start", &[]),
+            );
+        } else {
+            match PersonalBests::load(Path::new(SPLITS_FILE)) {
+                Ok(loaded) => bests = loaded,
+                Err(error) => view.log.push(
+                    world.turn,
+                    Category::Warning,
+                    catalog.message("splits-load-failed", &[("error", error.to_string())]),
+                ),
+            }
+            view.timer = Some(Timer::start(&world, &bests));
+        }
+    }
+    render(&world, stdout, &view, catalog)?;
+
+    let mut actions = Vec::new();
+    // with --dev, the content reloaded while the game runs
+    let mut watch = files.dev.then(|| Watch::new(files));
+    let mut reloaded = None;
 
     'game_loop: loop {
         // Read user input after rendering the world
-        if let Some(key_event) = keys.next() {
-            let key_event = key_event?;
-            match key_event {
-                Key::Up | Key::Down | Key::Left | Key::Right => {
-                    status_message = move_player(&mut world, key_event);
+        let Some(input) = keys.next() else {
+            return Ok(None);
+        };
+        let key = match input? {
+            Input::Key(key) => key,
+            Input::Reload => {
+                if let Some(watch) = &mut watch {
+                    hot_reload(watch, setup, &mut world, &mut view, &mut reloaded);
                 }
-                Key::Esc => break 'game_loop, // Exit game loop
-                _ => {}
+                let catalog = reloaded.as_ref().map_or(catalog, |(_, catalog)| catalog);
+                render(&world, stdout, &view, catalog)?;
+                continue 'game_loop;
+            }
+        };
+        let (content, catalog) = match &reloaded {
+            Some((content, catalog)) => (content, catalog),
+            None => (setup.content, catalog),
+        };
+        let Some(action) = keybindings.action_for(key) else {
+            continue 'game_loop; // Unbound keys do not take a turn
+        };
+        match action {
+            Action::MessageLog => {
+                message_log::show_history(stdout, &mut KeysOnly(keys), &view.log, catalog)?;
+                render(&world, stdout, &view, catalog)?;
+                continue 'game_loop;
             }
+            Action::Help => {
+                keybindings::show_help(stdout, &mut KeysOnly(keys), keybindings, catalog)?;
+                render(&world, stdout, &view, catalog)?;
+                continue 'game_loop;
+            }
+            Action::Stats => {
+                profile::show(stdout, &mut KeysOnly(keys), &world.stats, profile, catalog)?;
+                render(&world, stdout, &view, catalog)?;
+                continue 'game_loop;
+            }
+            Action::TogglePanel => {
+                view.show_panel = !view.show_panel;
+                render(&world, stdout, &view, catalog)?;
+                continue 'game_loop;
+            }
+            Action::Save => {
+                let outcome = match world.save(Path::new(SAVE_FILE)) {
+                    Ok(()) => catalog.message("game-saved", &[]),
+                    Err(error) => catalog.message("save-failed", &[("error", error.to_string())]),
+                };
+                view.log.push(world.turn, Category::Info, outcome);
+                render(&world, stdout, &view, catalog)?;
+                continue 'game_loop;
+            }
+            Action::Quit => return Ok(None), // Exit game loop
+            Action::Wait => {}
+            _ if action.direction().is_some() => {}
+            _ => continue 'game_loop,
+        }
+        actions.push(action);
+
+        let Turn {
+            outcome,
+            events,
+            output,
+        } = play_turn(&mut world, content, action);
+        log_turn(&mut view.log, world.turn, outcome, output, catalog);
+        let mut listeners = Listeners {
+            world: &world,
+            view: &mut view,
+            profile,
+            bests: &mut bests,
+            catalog,
+            bell: false,
+        };
+        screen_bus().publish_all(&mut listeners, &events);
+        if listeners.bell {
+            write!(stdout, "\x07")?;
         }
 
-        move_npcs(&mut world);
         // render world
-        render(&world, stdout, &status_message)?;
+        let won = world.stats.won_at.is_some();
+        render(&world, stdout, &view, catalog)?;
 
-        // handle player death
-        if world.player.dead {
-            while let Some(key_event) = keys.next() {
-                if let Ok(Key::Char('\n')) = key_event {
-                    break 'game_loop;
+        // handle player death or victory
+        if world.player.dead || won {
+            // keep the last message on screen until the player presses Enter
+            for key_event in KeysOnly(keys) {
+                if let Key::Char('\n') = key_event? {
+                    break;
                 }
             }
-            break 'game_loop;
+            return Ok(Some(Summary::new(world, actions)));
         }
 
         thread::sleep(Duration::from_millis(10));
     }
+}
 
-    Ok(())
+// What a turn did
+pub struct Turn {
+    // what the player's move did, nothing when they waited
+    pub outcome: Option<Outcome>,
+    pub events: Vec<GameEvent>,
+    pub output: scripting::Output,
+}
+
+// Who follows the events of the game core, whatever plays the game
+fn core_bus() -> EventBus<World> {
+    let mut bus = EventBus::new();
+    bus.subscribe(|world: &mut World, event| world.stats.record_event(event, world.turn));
+    bus
+}
+
+// What the subscribers of the game screen keep up to date
+struct Listeners<'a, 'k> {
+    world: &'a World,
+    view: &'a mut View<'k>,
+    profile: &'a mut Profile,
+    bests: &'a mut PersonalBests,
+    catalog: &'a Catalog,
+    // set when the terminal bell should ring
+    bell: bool,
+}
+
+// Who follows the events of the game played on screen
+fn screen_bus<'a, 'k>() -> EventBus<Listeners<'a, 'k>> {
+    let mut bus = EventBus::new();
+    bus.subscribe(record_split);
+    bus.subscribe(ring_bell);
+    bus.subscribe(unlock_achievements);
+    bus
+}
+
+// Log the milestones the speedrun timer reaches, and keep a won run beating
+// the personal best on this seed
+fn record_split(listeners: &mut Listeners, event: &GameEvent) {
+    let (turn, catalog) = (listeners.world.turn, listeners.catalog);
+    let view = &mut *listeners.view;
+    let Some(timer) = &mut view.timer else {
+        return;
+    };
+    if let Some(time) = timer.record(event, turn) {
+        let mut text = catalog.message(
+            "timer-split",
+            &[
+                ("split", catalog.message(time.split.message_id(), &[])),
+                ("time", format_time(time.millis)),
+            ],
+        );
+        if let Some(delta) = timer.delta(time) {
+            text.push_str(&format!(" ({})", delta));
+        }
+        view.log.push(turn, Category::Info, text);
+    }
+    if matches!(event, GameEvent::GameWon) && timer.finish(listeners.bests) {
+        let text = match listeners.bests.save(Path::new(SPLITS_FILE)) {
+            Ok(()) => catalog.message("timer-personal-best", &[]),
+            Err(error) => catalog.message("splits-save-failed", &[("error", error.to_string())]),
+        };
+        view.log.push(turn, Category::Loot, text);
+    }
+}
+
+// Sound the terminal bell when something noteworthy happens
+fn ring_bell(listeners: &mut Listeners, event: &GameEvent) {
+    match event {
+        GameEvent::NpcKilled { .. }
+        | GameEvent::ItemAcquired(_)
+        | GameEvent::PlayerDied
+        | GameEvent::GameWon => listeners.bell = true,
+        GameEvent::PlayerMoved { .. } | GameEvent::TileChanged { .. } => {}
+    }
+}
+
+fn unlock_achievements(listeners: &mut Listeners, _: &GameEvent) {
+    let catalog = listeners.catalog;
+    for achievement in listeners.profile.unlock(listeners.world) {
+        listeners.view.log.push(
+            listeners.world.turn,
+            Category::Loot,
+            catalog.message(
+                "achievement-unlocked",
+                &[("name", achievement.name(catalog))],
+            ),
+        );
+    }
+}
+
+// Let the bot play the world on screen until it wins, dies or runs out of
+// turns, or a key is pressed. `keys` must not wait for one.
+pub fn watch<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+    stdout: &mut W,
+    keys: &mut I,
+    mut world: World,
+    setup: &Setup,
+) -> Result<(), io::Error> {
+    let &Setup {
+        settings,
+        keybindings,
+        content,
+        catalog,
+        ..
+    } = setup;
+    let mut view = View {
+        log: MessageLog::new(MESSAGE_LOG_CAPACITY),
+        tileset: settings.tileset,
+        keybindings,
+        show_panel: true,
+        timer: None,
+    };
+    let mut goal = None;
+    while !world.player.dead && world.stats.won_at.is_none() && world.turn < bot::TURN_BUDGET {
+        if let Some(key) = keys.next() {
+            key?;
+            return Ok(());
+        }
+        let next = bot::current_goal(&world);
+        if goal != Some(next) {
+            goal = Some(next);
+            view.log.push(
+                world.turn,
+                Category::Info,
+                catalog.message(next.message_id(), &[]),
+            );
+        }
+        let action = bot::next_action(&world);
+        let Turn {
+            outcome, output, ..
+        } = play_turn(&mut world, content, action);
+        log_turn(&mut view.log, world.turn, outcome, output, catalog);
+        render(&world, stdout, &view, catalog)?;
+        thread::sleep(BOT_TURN_DELAY);
+    }
+    // keep the end on screen until a key is pressed
+    loop {
+        if let Some(key) = keys.next() {
+            key?;
+            return Ok(());
+        }
+        thread::sleep(BOT_TURN_DELAY);
+    }
 }
 
-fn move_player(world: &mut World, key_event: Key) -> String {
-    let (dx, dy) = match key_event {
-        Key::Up => (0, -1),
-        Key::Down => (0, 1),
-        Key::Left => (-1, 0),
-        Key::Right => (1, 0),
-        _ => (0, 0),
+// Tell the player what the turn did
+fn log_turn(
+    log: &mut MessageLog,
+    turn: u32,
+    outcome: Option<Outcome>,
+    output: scripting::Output,
+    catalog: &Catalog,
+) {
+    if let Some(outcome) = outcome {
+        log.push(turn, outcome.category(), outcome.message(catalog));
+    }
+    for text in output.messages {
+        log.push(turn, Category::Info, text);
+    }
+    for error in output.errors {
+        log.push(
+            turn,
+            Category::Warning,
+            catalog.message("script-error", &[("error", error)]),
+        );
+    }
+}
+
+// Play the player's action, a move or waiting, then the turns of the NPCs,
+// the weather and the scripts. This is the whole game without the screen.
+pub fn play_turn(world: &mut World, content: &Content, action: Action) -> Turn {
+    let (outcome, cost) = match action.direction() {
+        Some((dx, dy)) => {
+            let outcome = move_player(world, &content.rules, dx, dy);
+            world.stats.record_outcome(&outcome);
+            let cost = scheduler::action_cost(&outcome);
+            (Some(outcome), cost)
+        }
+        None => (None, ACTION_COST),
     };
+    world.turn += 1;
+
+    // at the speed of the tile the action took the player to
+    let player_speed = scheduler::player_speed(world);
+    move_npcs(world, scheduler::elapsed_time(cost, player_speed));
+    weather::update(world);
+    let mut events = world.take_events();
+    let output = content.scripts.run(world, &events);
+    // what the scripts changed counts for this turn, without calling their hooks again
+    events.extend(world.take_events());
+    core_bus().publish_all(world, &events);
+    Turn {
+        outcome,
+        events,
+        output,
+    }
+}
+
+fn move_player(world: &mut World, rules: &Rules, dx: i32, dy: i32) -> Outcome {
     let destination_x = world.player.x + dx;
     let destination_y = world.player.y + dy;
 
@@ -66,204 +504,260 @@ fn move_player(world: &mut World, key_event: Key) -> String {
         || destination_x >= TERRAIN_WIDTH as i32
         || destination_y >= TERRAIN_HEIGHT as i32
     {
-        return "Stay with us, don't try to leave".to_string(); // Prevent moving out of bounds
+        return Outcome::OutOfBounds; // Prevent moving out of bounds
     }
 
-    match world.terrain.tiles[destination_y as usize * TERRAIN_WIDTH + destination_x as usize] {
-        TileType::Grass | TileType::Sand => {
-            world.player.x = destination_x;
-            world.player.y = destination_y;
-            // check for troll to interact with it
-            if let Some(npc_index) = world.npcs.iter().position(|npc| {
-                npc.x == destination_x && npc.y == destination_y && npc.npc_type == NPCType::Troll
-            }) {
-                if world.player.inventory.contains(&ItemType::Sword) {
-                    world.npcs.remove(npc_index); // Remove the troll NPC from the game
-                    if !world.player.inventory.contains(&ItemType::Axe) {
-                        world.player.inventory.push(ItemType::Axe);
-                        return "You bravely fight and defeat the troll! He leaves an Axe on the ground!".to_string();
-                    } else {
-                        return "You bravely fight and defeat the troll!".to_string();
-                    }
-                } else {
-                    return "There's a troll here! You need a sword to fight!".to_string();
-                }
-            }
-            // Check for spider to interact with it
-            if let Some(npc_index) = world.npcs.iter().position(|npc| {
-                npc.x == destination_x && npc.y == destination_y && npc.npc_type == NPCType::Spider
-            }) {
-                world.npcs.remove(npc_index); // Remove the spider NPC from the game
-                world.player.inventory.push(ItemType::Snorkel); // Loot a snorkel
-                return "You've defeated the spider and found a snorkel on its body!".to_string();
+    // creatures are met on the tiles they live on, a spider on its web is not
+    let tile = world.terrain.tiles[destination_y as usize * TERRAIN_WIDTH + destination_x as usize];
+    if let Some(npc_index) = world.npcs.iter().position(|npc| {
+        npc.x == destination_x && npc.y == destination_y && npc.npc_type.allowed_tile() == tile
+    }) {
+        return meet_npc(world, npc_index);
+    }
+    // what the tiles do is up to the interaction rules
+    rules
+        .apply(world, destination_x, destination_y)
+        .unwrap_or(Outcome::Unreachable)
+}
+
+fn meet_npc(world: &mut World, npc_index: usize) -> Outcome {
+    let NPC { x, y, npc_type, .. } = world.npcs[npc_index];
+    // the NPCs of mods fight like the built-in ones they are like
+    match npc_type.like() {
+        NPCType::TROLL => {
+            if !world.player.has(ItemType::SWORD) {
+                return Outcome::TrollBlocksPath;
             }
-            return "You are wandering around!".to_string();
-        }
-        // player can move on water only if he has the snorkel in his inventory
-        TileType::Water => {
-            // check for fish to interact with it
-            if let Some(npc_index) = world.npcs.iter().position(|npc| {
-                npc.x == destination_x && npc.y == destination_y && npc.npc_type == NPCType::Fish
-            }) {
-                if world.player.inventory.contains(&ItemType::Harpoon) {
-                    if world.player.inventory.contains(&ItemType::Key) {
-                        return "You don't want to fish anymore!".to_string();
-                    } else {
-                        world.npcs.remove(npc_index); // Remove the fish NPC from the game
-                        world.player.inventory.push(ItemType::Key);
-                        return "You catch a fish with your harpoon! When you look inside the fish, you find a key.. Looks like the fish had something weird for dinner!".to_string();
-                    }
-                } else {
-                    return "You see a fish swimming by, but you have no tool to catch it!"
-                        .to_string();
-                }
+            world.move_player_to(x, y);
+            world.kill_npc(npc_index); // Remove the troll NPC from the game
+            if world.player.has(ItemType::AXE) {
+                Outcome::TrollDefeated { loot: None }
             } else {
-                if world.player.inventory.contains(&ItemType::Snorkel) {
-                    world.player.x = destination_x;
-                    world.player.y = destination_y;
-                    return "You are swimming like a cute little fish !".to_string();
-                } else {
-                    return "You cannot enter like this in the water, look around for something that may help you!".to_string();
+                world.give_item(ItemType::AXE);
+                Outcome::TrollDefeated {
+                    loot: Some(ItemType::AXE),
                 }
             }
         }
-        TileType::Tree => {
-            if world.player.inventory.contains(&ItemType::Axe) {
-                let mut rng = rand::thread_rng();
-                let destination_idx =
-                    destination_y as usize * TERRAIN_WIDTH + destination_x as usize;
-                if rng.gen_range(0..100) < 15 && !world.terrain.chest_found {
-                    // 15% chance to replace the tree with a chest
-                    world.terrain.tiles[destination_idx] = TileType::Chest;
-                    world.terrain.chest_found = true;
-                    return "You chopped a tree... And found a chest!".to_string();
-                } else {
-                    // Otherwise, replace it with grass
-                    world.terrain.tiles[destination_idx] = TileType::Grass;
-                    return "You chopped a tree!".to_string();
+        NPCType::SPIDER => {
+            world.move_player_to(x, y);
+            world.kill_npc(npc_index); // Remove the spider NPC from the game
+            if world.player.has(ItemType::SNORKEL) {
+                Outcome::SpiderDefeated { loot: None }
+            } else {
+                world.give_item(ItemType::SNORKEL); // Loot a snorkel
+                Outcome::SpiderDefeated {
+                    loot: Some(ItemType::SNORKEL),
                 }
             }
-            return "You gonna need a tool if you want to interact with a tree!".to_string();
         }
-        TileType::Chest => {
-            if world.player.inventory.contains(&ItemType::Harpoon) {
-                return "Chest is empty. You already took the harpoon that was inside.".to_string();
+        NPCType::FISH => {
+            if !world.player.has(ItemType::HARPOON) {
+                Outcome::FishOutOfReach
+            } else if world.player.has(ItemType::KEY) {
+                Outcome::FishIgnored
             } else {
-                world.player.inventory.push(ItemType::Harpoon);
-                return "You found a harpoon, but for what ?".to_string();
-            }
-        }
-        TileType::Castle => {
-            if world.player.inventory.contains(&ItemType::Key) {
-                world.terrain.tiles
-                    [destination_y as usize * TERRAIN_WIDTH + destination_x as usize] =
-                    TileType::Heart;
-                return "You enter the castle and there is a beautiful princess inside, you found love and are virtually happy for the rest of your virtual life. You can exit the simulation! Press Escape..".to_string();
+                world.kill_npc(npc_index); // Remove the fish NPC from the game
+                world.give_item(ItemType::KEY);
+                Outcome::FishCaught {
+                    loot: ItemType::KEY,
+                }
             }
-            return "Castle door is locked!".to_string();
-        }
-        TileType::SpiderWeb => {
-            world.player.dead = true;
-            return "It's a trap!!! You are trapped into the spider web, the spider is gonna come back soon and eat you alive. You die. Press Enter to continue...".to_string();
-        }
-        _ => {
-            return "THIS MESSAGE SHOULD NEVER APPEAR, CONTACT THE GAME DEVELOPER!".to_string();
         }
+        _ => Outcome::Unreachable,
     }
 }
 
-pub fn move_npcs(world: &mut World) {
-    let mut rng = rand::thread_rng();
+// Let the NPCs take the turns they earned while `elapsed` time passed
+pub fn move_npcs(world: &mut World, elapsed: u32) {
+    let phase = world.clock().phase();
 
-    'npc_loop: for npc in &mut world.npcs {
+    'npc_loop: for npc_index in scheduler::schedule(&mut world.npcs, elapsed) {
+        let npc = &mut world.npcs[npc_index];
         // Generate a random number between 0 and 99 (inclusive)
-        if rng.gen_range(0..100) < 10 {
-            // 10% chance to not move
+        if world.rng.gen_range(0..100) < world.balance.idle_chance {
+            // some chance to not move
             continue;
         }
-        // Check if the NPC is a spider and generate a random number
-        if npc.npc_type == NPCType::Spider && rng.gen_range(0..100) < 15 {
-            // 15% chance to change the current tile to SpiderWeb
-            let index = (npc.y as usize) * TERRAIN_WIDTH + npc.x as usize;
-            world.terrain.tiles[index] = TileType::SpiderWeb;
-        }
+        // Spiders weave twice as many webs after dark
+        let web_chance = if phase == DayPhase::Night {
+            (world.balance.web_chance * 2).min(100)
+        } else {
+            world.balance.web_chance
+        };
+        // Check if the NPC is a spider and generate a random number, web_chance
+        // to weave a web on the tile it leaves
+        let weaves =
+            npc.npc_type.like() == NPCType::SPIDER && world.rng.gen_range(0..100) < web_chance;
 
         let mut directions = [(0, -1), (1, 0), (0, 1), (-1, 0)]; // Represents up, right, down, left
-        directions.shuffle(&mut rng); // Shuffle the directions
+        directions.shuffle(&mut world.rng); // Shuffle the directions
 
-        for &(dx, dy) in &directions {
-            let new_x = npc.x + dx;
-            let new_y = npc.y + dy;
-
-            // Check if new position is player's position
-            if new_x == world.player.x && new_y == world.player.y {
-                continue; // Skip if new position is where the player is
-            }
+        let NPC { x, y, npc_type, .. } = world.npcs[npc_index];
+        // At night trolls hunt the player they can see: try the steps getting closer first
+        let distance_to_player =
+            |x: i32, y: i32| (x - world.player.x).pow(2) + (y - world.player.y).pow(2);
+        if npc_type.like() == NPCType::TROLL
+            && phase == DayPhase::Night
+            && distance_to_player(x, y) <= TROLL_HUNT_RADIUS * TROLL_HUNT_RADIUS
+        {
+            directions.sort_by_key(|&(dx, dy)| distance_to_player(x + dx, y + dy));
+        }
 
-            // Check bounds and movable tile type
-            if new_x >= 0
-                && new_x < TERRAIN_WIDTH as i32
-                && new_y >= 0
-                && new_y < TERRAIN_HEIGHT as i32
-            {
-                let index = (new_y as usize) * TERRAIN_WIDTH + new_x as usize;
-                if world.terrain.tiles[index] == npc.npc_type.allowed_tile() {
-                    npc.x = new_x;
-                    npc.y = new_y;
-                    continue 'npc_loop; // Move successful, next npc
+        for &(dx, dy) in &directions {
+            let new_x = x + dx;
+            let new_y = y + dy;
+            if open_to(world, npc_type, new_x, new_y) {
+                let npc = &mut world.npcs[npc_index];
+                npc.x = new_x;
+                npc.y = new_y;
+                if weaves {
+                    weave(world, x, y);
                 }
+                continue 'npc_loop; // Move successful, next npc
             }
         }
         // If no valid move is found, the NPC stays in place.
     }
 }
 
-fn render<W: Write>(world: &World, stdout: &mut W, status_message: &str) -> Result<(), io::Error> {
-    // Get the terminal size
-    let (_, term_height) = terminal_size()?;
+// Whether an NPC of the type may step on the tile: on the map, one it lives
+// on, and neither the player nor another NPC there, they never share a tile
+fn open_to(world: &World, npc_type: NPCType, x: i32, y: i32) -> bool {
+    if x < 0 || x >= TERRAIN_WIDTH as i32 || y < 0 || y >= TERRAIN_HEIGHT as i32 {
+        return false;
+    }
+    (x, y) != (world.player.x, world.player.y)
+        && !world.npcs.iter().any(|other| other.x == x && other.y == y)
+        && world.terrain.tiles[y as usize * TERRAIN_WIDTH + x as usize] == npc_type.allowed_tile()
+}
 
-    // Calculate the positions for documentation and status message
-    let _ = term_height - 1; // Assuming the status bar is at the bottom
+// Turn the tile the spider left into a web, unless the web would cut the
+// mainland in two: walled in by its webs the spider could never be met, and
+// neither could what lies behind them
+fn weave(world: &mut World, x: i32, y: i32) {
+    let index = (y as usize) * TERRAIN_WIDTH + x as usize;
+    let from = world.terrain.tiles[index];
+    if from == TileType::SPIDER_WEB {
+        return;
+    }
+    let mainland = |terrain: &Terrain| terrain.mainland().iter().filter(|&&tile| tile).count();
+    let before = mainland(&world.terrain);
+    world.terrain.tiles[index] = TileType::SPIDER_WEB;
+    if mainland(&world.terrain) + 1 < before {
+        world.terrain.tiles[index] = from;
+        return;
+    }
+    world.events.push(GameEvent::TileChanged {
+        x,
+        y,
+        from,
+        to: TileType::SPIDER_WEB,
+    });
+}
 
-    // draw the documentation on top
-    let documentation = "This area displays helpful\r\ninformation about the game.";
+// Apply what --dev reloaded to the running game, and tell the player. What
+// fails to load is reported and the game goes on without it.
+fn hot_reload(
+    watch: &mut Watch,
+    setup: &Setup,
+    world: &mut World,
+    view: &mut View,
+    reloaded: &mut Option<(Content, Catalog)>,
+) {
+    let results = watch.reload(
+        setup.files,
+        world.balance.difficulty,
+        setup.catalog.language(),
+    );
+    let mut notes = Vec::new();
+    for result in results {
+        match result {
+            Ok(reload) => {
+                notes.push(Ok(reload.message_id()));
+                match reload {
+                    Reloaded::Balance(balance) => world.balance = balance,
+                    Reloaded::Content(content, catalog) => *reloaded = Some((*content, catalog)),
+                    Reloaded::Theme(tileset) => view.tileset = tileset,
+                }
+            }
+            Err(error) => notes.push(Err(error)),
+        }
+    }
+    let catalog = reloaded
+        .as_ref()
+        .map_or(setup.catalog, |(_, catalog)| catalog);
+    for note in notes {
+        match note {
+            Ok(message_id) => {
+                view.log
+                    .push(world.turn, Category::Info, catalog.message(message_id, &[]))
+            }
+            Err(error) => view.log.push(
+                world.turn,
+                Category::Warning,
+                catalog.message("dev-reload-failed", &[("error", error)]),
+            ),
+        }
+    }
+}
 
-    // Prepare full frame in a buffer
-    let mut frame = format!("{}\r\n\r\n", documentation);
+fn render<W: Write>(
+    world: &World,
+    stdout: &mut W,
+    view: &View,
+    catalog: &Catalog,
+) -> Result<(), io::Error> {
+    let tileset = view.tileset;
+
+    // Prepare full frame in a buffer, starting with the help panel on top
+    let mut frame = String::new();
+    if view.show_panel {
+        frame.push_str(&panel::draw(world, tileset, view.keybindings, catalog));
+        frame.push_str("\r\n");
+    }
 
     // Draw the world into the buffer
-    frame.push_str(&world.draw_to_string());
+    frame.push_str(&world.draw_to_string(tileset));
 
     // Build player inventory str
-    let mut inventory: String = String::from("\x1b[1m\x1b[93mPlayer Inventory:\x1b[0m ");
-    for item in &world.player.inventory {
-        match item {
-            ItemType::Sword => {
-                inventory.push_str("🗡️  ");
-            }
-            ItemType::Axe => {
-                inventory.push_str("🪓  ");
-            }
-            ItemType::Snorkel => {
-                inventory.push_str("🤿  ");
-            }
-            ItemType::Harpoon => {
-                inventory.push_str("🔱  ");
-            }
-            ItemType::Key => {
-                inventory.push_str("🗝️  ");
-            }
-        }
+    let mut inventory = format!(
+        "\x1b[1m\x1b[93m{}\x1b[0m ",
+        catalog.message("hud-inventory", &[])
+    );
+    for &item in &world.player.inventory {
+        inventory.push_str(tileset.item(item));
+        inventory.push(' ');
     }
     // draw player inventory into the buffer
     frame.push_str(&format!("\r\n{}\r\n", inventory));
 
-    // Draw the status message at the bottom into the buffer
-    frame.push_str(&format!("\r\n{}\r\n", status_message));
+    // Draw the time of day and the weather
+    let clock = world.clock();
+    frame.push_str(&format!(
+        "\x1b[1m\x1b[93m{}\x1b[0m {} {}   \x1b[1m\x1b[93m{}\x1b[0m {}\r\n",
+        catalog.message("hud-time", &[]),
+        clock.format(),
+        catalog.message(clock.phase().message_id(), &[]),
+        catalog.message("hud-weather", &[]),
+        catalog.message(world.weather.message_id(), &[])
+    ));
+    if let Some(timer) = &view.timer {
+        frame.push_str(&format!(
+            "\x1b[1m\x1b[93m{}\x1b[0m {}\r\n",
+            catalog.message("hud-timer", &[]),
+            timer.hud(catalog)
+        ));
+    }
+
+    // Draw the latest messages at the bottom into the buffer
+    frame.push_str("\r\n");
+    for entry in view.log.last(HUD_LOG_LINES) {
+        frame.push_str(&format!("{}\r\n", entry.format()));
+    }
 
     // Clear the screen and reset cursor position
-    write!(stdout, "{}{}", clear::All, cursor::Goto(1, 1))?;
-    print!("{}", frame);
+    write!(stdout, "{}{}{}", clear::All, cursor::Goto(1, 1), frame)?;
 
     // Flush stdout to ensure that all terminal output is displayed
     stdout.flush()?;
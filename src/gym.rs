@@ -0,0 +1,325 @@
+use crate::events::GameEvent;
+use crate::game::{self, Content, Turn};
+use crate::keybindings::Action;
+use crate::outcome::Outcome;
+use crate::world::entities::ItemType;
+use crate::world::terrain::{TERRAIN_HEIGHT, TERRAIN_WIDTH};
+use crate::world::{Balance, World};
+
+// The game as an environment for agents, in the style of OpenAI Gym. Nothing
+// is drawn, a turn is played for every step:
+//
+//   let mut env = Env::new(Content::default(), Balance::default());
+//   let mut observation = env.reset(42);
+//   loop {
+//       let (next, reward, done, info) = env.step(policy(&observation));
+//       if done {
+//           break;
+//       }
+//       observation = next;
+//   }
+//
+// Rewards come from hooks called on the milestones of every step, see
+// `Milestone`, which `on_milestone` adds to the default ones.
+
+// The actions an agent picks from, `ACTIONS[n]` for a discrete action space
+pub const ACTIONS: [Action; 9] = [
+    Action::MoveNorth,
+    Action::MoveSouth,
+    Action::MoveWest,
+    Action::MoveEast,
+    Action::MoveNorthWest,
+    Action::MoveNorthEast,
+    Action::MoveSouthWest,
+    Action::MoveSouthEast,
+    Action::Wait,
+];
+
+// Episodes not over after this many turns are cut short
+pub const MAX_TURNS: u32 = 5000;
+
+// What the reward hooks are called for
+pub enum Milestone<'a> {
+    // what the move did, the message id tells which: "troll-defeated",
+    // "chest-uncovered", "tree-chopped", "wandering"...
+    Outcome(&'a Outcome),
+    Won,
+    Died,
+}
+
+pub type RewardHook = Box<dyn FnMut(&Milestone, &World) -> f64>;
+
+// Everything the agent sees, as numbers
+#[derive(Clone, PartialEq)]
+pub struct Observation {
+    pub width: usize,
+    pub height: usize,
+    // the index of the tile type of every tile, row by row, see TileType::index
+    pub tiles: Vec<u16>,
+    // x, y
+    pub player: [i32; 2],
+    // NPC type index, x, y of every NPC
+    pub npcs: Vec<[i32; 3]>,
+    // how many of every item type the player holds, by ItemType::index
+    pub inventory: Vec<u32>,
+    pub turn: u32,
+}
+
+impl Observation {
+    pub fn of(world: &World) -> Observation {
+        let mut inventory = vec![0; ItemType::all().count()];
+        for item in &world.player.inventory {
+            inventory[item.index()] += 1;
+        }
+        Observation {
+            width: TERRAIN_WIDTH,
+            height: TERRAIN_HEIGHT,
+            tiles: world
+                .terrain
+                .tiles
+                .iter()
+                .map(|tile| tile.index() as u16)
+                .collect(),
+            player: [world.player.x, world.player.y],
+            npcs: world
+                .npcs
+                .iter()
+                .map(|npc| [npc.npc_type.index() as i32, npc.x, npc.y])
+                .collect(),
+            inventory,
+            turn: world.turn,
+        }
+    }
+}
+
+// What the step did, beyond the observation
+pub struct Info {
+    // message id of what the move did, none when the agent waited
+    pub outcome: Option<String>,
+    pub events: Vec<GameEvent>,
+    pub won: bool,
+    pub dead: bool,
+    // the episode reached MAX_TURNS
+    pub truncated: bool,
+}
+
+pub struct Env {
+    world: World,
+    content: Content,
+    balance: Balance,
+    rewards: Vec<RewardHook>,
+}
+
+impl Env {
+    // An environment playing generated worlds with the content and balance.
+    // Call `reset` before the first step.
+    pub fn new(content: Content, balance: Balance) -> Env {
+        Env {
+            world: World::with_seed(0, balance),
+            content,
+            balance,
+            rewards: vec![Box::new(default_reward)],
+        }
+    }
+
+    // Add a hook, its rewards are summed with the other hooks'
+    pub fn on_milestone(&mut self, hook: impl FnMut(&Milestone, &World) -> f64 + 'static) {
+        self.rewards.push(Box::new(hook));
+    }
+
+    // Remove every hook, the default one included
+    pub fn clear_rewards(&mut self) {
+        self.rewards.clear();
+    }
+
+    // Start a new episode in the world of the seed
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.world = World::with_seed(seed, self.balance);
+        Observation::of(&self.world)
+    }
+
+    // Play a turn. Actions that do not take a turn in the game are waits, and
+    // once the episode is done the world no longer changes.
+    pub fn step(&mut self, action: Action) -> (Observation, f64, bool, Info) {
+        let mut info = Info {
+            outcome: None,
+            events: Vec::new(),
+            won: self.won(),
+            dead: self.world.player.dead,
+            truncated: self.world.turn >= MAX_TURNS,
+        };
+        if info.won || info.dead || info.truncated {
+            return (Observation::of(&self.world), 0.0, true, info);
+        }
+
+        let action = if action.direction().is_some() {
+            action
+        } else {
+            Action::Wait
+        };
+        let Turn {
+            outcome, events, ..
+        } = game::play_turn(&mut self.world, &self.content, action);
+        let mut milestones = Vec::new();
+        if let Some(outcome) = &outcome {
+            milestones.push(Milestone::Outcome(outcome));
+        }
+        info.won = self.won();
+        info.dead = self.world.player.dead;
+        info.truncated = self.world.turn >= MAX_TURNS;
+        if info.won {
+            milestones.push(Milestone::Won);
+        }
+        if info.dead {
+            milestones.push(Milestone::Died);
+        }
+        let mut reward = 0.0;
+        for milestone in &milestones {
+            for hook in &mut self.rewards {
+                reward += hook(milestone, &self.world);
+            }
+        }
+
+        info.outcome = outcome
+            .as_ref()
+            .map(|outcome| outcome.message_id().to_string());
+        info.events = events;
+        let done = info.won || info.dead || info.truncated;
+        (Observation::of(&self.world), reward, done, info)
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    fn won(&self) -> bool {
+        self.world.stats.won_at.is_some()
+    }
+}
+
+// Winning is what counts, getting items and beating creatures on the way helps
+pub fn default_reward(milestone: &Milestone, _world: &World) -> f64 {
+    match milestone {
+        Milestone::Won => 100.0,
+        Milestone::Died => -100.0,
+        Milestone::Outcome(
+            Outcome::TrollDefeated { .. }
+            | Outcome::SpiderDefeated { .. }
+            | Outcome::FishCaught { .. },
+        ) => 10.0,
+        Milestone::Outcome(Outcome::Interaction(interaction)) => {
+            match interaction.message.as_str() {
+                "tree-chopped" => 1.0,
+                "chest-uncovered" | "chest-looted" => 10.0,
+                _ => 0.0,
+            }
+        }
+        Milestone::Outcome(_) => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::terrain::TileType;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // An episode of seed 3 with the player at (10, 10), no NPCs, and the tile
+    // east of them
+    fn next_to(east: TileType) -> Env {
+        let mut env = Env::new(Content::default(), Balance::default());
+        env.reset(3);
+        let world = &mut env.world;
+        (world.player.x, world.player.y) = (10, 10);
+        world.npcs.clear();
+        world.terrain.tiles[10 * TERRAIN_WIDTH + 11] = east;
+        env
+    }
+
+    // Replace the hooks with one giving 0.5 for every milestone, and return
+    // the milestones it saw
+    fn record(env: &mut Env) -> Rc<RefCell<Vec<String>>> {
+        env.clear_rewards();
+        let milestones = Rc::new(RefCell::new(Vec::new()));
+        let seen = milestones.clone();
+        env.on_milestone(move |milestone, _| {
+            seen.borrow_mut().push(match milestone {
+                Milestone::Outcome(outcome) => outcome.message_id().to_string(),
+                Milestone::Won => "won".to_string(),
+                Milestone::Died => "died".to_string(),
+            });
+            0.5
+        });
+        milestones
+    }
+
+    #[test]
+    fn episodes_replay_with_the_seed() {
+        let mut env = Env::new(Content::default(), Balance::default());
+        let first = env.reset(7);
+        assert_eq!(first.tiles.len(), first.width * first.height);
+        assert_eq!(first.inventory[ItemType::SWORD.index()], 1);
+
+        let play = |env: &mut Env| {
+            let mut observations = vec![env.reset(7)];
+            for turn in 0..200 {
+                let (observation, _, done, _) = env.step(ACTIONS[turn % ACTIONS.len()]);
+                observations.push(observation);
+                if done {
+                    break;
+                }
+            }
+            observations
+        };
+        let observations = play(&mut env);
+        assert!(observations[0] == first);
+        assert!(observations == play(&mut env));
+    }
+
+    #[test]
+    fn hooks_fire_on_every_move() {
+        let mut env = next_to(TileType::GRASS);
+        let milestones = record(&mut env);
+        let (_, reward, done, info) = env.step(Action::Wait);
+        assert!(reward == 0.0 && !done && info.outcome.is_none());
+        let (observation, reward, done, info) = env.step(Action::MoveEast);
+        assert_eq!(info.outcome.as_deref(), Some("wandering"));
+        assert_eq!(observation.player, [11, 10]);
+        assert!(reward == 0.5 && !done);
+        assert_eq!(*milestones.borrow(), ["wandering"]);
+    }
+
+    #[test]
+    fn winning_and_dying_reach_the_hooks() {
+        let mut env = next_to(TileType::CASTLE);
+        env.world.player.inventory.push(ItemType::KEY);
+        let milestones = record(&mut env);
+        let (_, reward, done, info) = env.step(Action::MoveEast);
+        assert!(reward == 1.0 && done && info.won && !info.dead);
+        assert_eq!(*milestones.borrow(), ["castle-opened", "won"]);
+
+        let mut env = next_to(TileType::SPIDER_WEB);
+        let milestones = record(&mut env);
+        let (_, reward, done, info) = env.step(Action::MoveEast);
+        assert!(reward == 1.0 && done && info.dead && !info.won);
+        assert_eq!(*milestones.borrow(), ["trapped-in-web", "died"]);
+        // and the default hook punishes it
+        let mut env = next_to(TileType::SPIDER_WEB);
+        let (_, reward, _, _) = env.step(Action::MoveEast);
+        assert!(reward == -100.0);
+    }
+
+    #[test]
+    fn episodes_are_cut_short_at_the_limit() {
+        let mut env = next_to(TileType::GRASS);
+        env.world.turn = MAX_TURNS - 1;
+        let (_, _, done, info) = env.step(Action::Wait);
+        assert!(done && info.truncated && !info.won && !info.dead);
+        // nothing changes once the episode is done
+        let (observation, reward, done, info) = env.step(Action::MoveEast);
+        assert!(done && info.truncated && reward == 0.0);
+        assert_eq!(observation.turn, MAX_TURNS);
+        assert_eq!(observation.player, [10, 10]);
+    }
+}
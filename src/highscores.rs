@@ -0,0 +1,381 @@
+use crate::game::{self, Content, Summary};
+use crate::i18n::Catalog;
+use crate::keybindings::Action;
+use crate::world::{Difficulty, SaveGame, World};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use termion::{clear, cursor, event::Key};
+
+// Default location of the high-score table
+pub const HIGHSCORES_FILE: &str = "highscores.json";
+// Directory the replays of the scored games are written to
+pub const REPLAYS_DIR: &str = "replays";
+// Entries kept for every seed and difficulty
+const TABLE_SIZE: usize = 10;
+
+// Winning is what counts, then winning fast. Items and kills earn some points
+// on the way, and dying costs some.
+pub fn score(summary: &Summary) -> u32 {
+    let mut score = 100 * summary.items.len() as u32 + 50 * summary.stats.kills();
+    if summary.won {
+        score += 1000 + 2000u32.saturating_sub(summary.turns);
+    } else {
+        score = score.saturating_sub(200);
+    }
+    score
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    pub turns: u32,
+    pub won: bool,
+    // YYYY-MM-DD
+    pub date: String,
+    // replay file of the game, if it could be written
+    pub replay: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Table {
+    seed: u64,
+    difficulty: Difficulty,
+    // best score first
+    entries: Vec<HighScore>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    tables: Vec<Table>,
+}
+
+impl HighScores {
+    // Load the high scores, or an empty table when there is no such file.
+    pub fn load(path: &Path) -> Result<HighScores, io::Error> {
+        if !path.exists() {
+            return Ok(HighScores::default());
+        }
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), error),
+            )
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)
+    }
+
+    // Whether the score would make the table of its seed and difficulty
+    pub fn qualifies(&self, seed: u64, difficulty: Difficulty, score: u32) -> bool {
+        self.tables
+            .iter()
+            .find(|table| table.seed == seed && table.difficulty == difficulty)
+            .is_none_or(|table| {
+                table.entries.len() < TABLE_SIZE
+                    || table.entries.iter().any(|entry| entry.score < score)
+            })
+    }
+
+    // Add the score to the table of its seed and difficulty. Returns its rank,
+    // starting at 1, or nothing when it does not make the table.
+    pub fn insert(&mut self, seed: u64, difficulty: Difficulty, entry: HighScore) -> Option<usize> {
+        let index = match self
+            .tables
+            .iter()
+            .position(|table| table.seed == seed && table.difficulty == difficulty)
+        {
+            Some(index) => index,
+            None => {
+                self.tables.push(Table {
+                    seed,
+                    difficulty,
+                    entries: Vec::new(),
+                });
+                self.tables.len() - 1
+            }
+        };
+        let entries = &mut self.tables[index].entries;
+        // ties go to the earlier score
+        let rank = entries
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(entries.len());
+        if rank >= TABLE_SIZE {
+            return None;
+        }
+        entries.insert(rank, entry);
+        entries.truncate(TABLE_SIZE);
+        Some(rank + 1)
+    }
+
+    // Full screen view of the tables, one seed and difficulty per page
+    pub fn show<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+        &self,
+        stdout: &mut W,
+        keys: &mut I,
+        catalog: &Catalog,
+    ) -> Result<(), io::Error> {
+        let mut page = 0;
+        loop {
+            let mut frame = format!(
+                "\x1b[1m\x1b[93m{}\x1b[0m\r\n\r\n",
+                catalog.message("scores-title", &[])
+            );
+            match self.tables.get(page) {
+                Some(table) => {
+                    frame.push_str(&format!(
+                        "{}\r\n\r\n",
+                        catalog.message(
+                            "scores-table",
+                            &[
+                                ("seed", table.seed.to_string()),
+                                (
+                                    "difficulty",
+                                    catalog.message(table.difficulty.message_id(), &[])
+                                ),
+                                ("page", (page + 1).to_string()),
+                                ("pages", self.tables.len().to_string()),
+                            ],
+                        )
+                    ));
+                    for (rank, entry) in table.entries.iter().enumerate() {
+                        let result = if entry.won {
+                            "scores-won"
+                        } else {
+                            "scores-died"
+                        };
+                        frame.push_str(&format!(
+                            "  {:>2}. {:<16} {:>6}  {:<10} {:>6} {}  {}\r\n",
+                            rank + 1,
+                            entry.name,
+                            entry.score,
+                            entry.date,
+                            entry.turns,
+                            catalog.message(result, &[]),
+                            entry
+                                .replay
+                                .as_ref()
+                                .map_or(String::new(), |path| path.display().to_string())
+                        ));
+                    }
+                }
+                None => frame.push_str(&format!("{}\r\n", catalog.message("scores-empty", &[]))),
+            }
+            frame.push_str(&format!("\r\n{}\r\n", catalog.message("scores-help", &[])));
+
+            write!(stdout, "{}{}{}", clear::All, cursor::Goto(1, 1), frame)?;
+            stdout.flush()?;
+
+            match keys.next() {
+                Some(Ok(Key::Left)) => page = page.saturating_sub(1),
+                Some(Ok(Key::Right)) if page + 1 < self.tables.len() => page += 1,
+                Some(Ok(Key::Esc | Key::Char('q'))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(error)) => return Err(error),
+            }
+        }
+    }
+}
+
+// The state a game started from, generator included, what it was played with
+// and the actions the player took in it, enough to play the game again turn
+// by turn
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub start: SaveGame,
+    // fingerprint of the content
+    pub content: u64,
+    pub actions: Vec<Action>,
+}
+
+impl Replay {
+    pub fn new(start: &World, content: &Content, actions: &[Action]) -> Replay {
+        Replay {
+            start: SaveGame::from_world(start),
+            content: content.fingerprint,
+            actions: actions.to_vec(),
+        }
+    }
+
+    // Write the replay in the replays directory and return its path
+    pub fn write(&self) -> Result<PathBuf, io::Error> {
+        fs::create_dir_all(REPLAYS_DIR)?;
+        let path = Path::new(REPLAYS_DIR).join(format!(
+            "{}-{}.json",
+            self.start.seed(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_millis())
+        ));
+        fs::write(&path, serde_json::to_string(self)?)?;
+        Ok(path)
+    }
+
+    pub fn read(path: &Path) -> Result<Replay, io::Error> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|error| error.to_string())
+            .and_then(|replay: Replay| replay.start.validate().map(|()| replay))
+            .map_err(|message| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), message),
+                )
+            })
+    }
+
+    // Play the actions again from the start, with the content the game was
+    // recorded with, and return how it ended
+    pub fn play(self, content: &Content) -> Result<Summary, String> {
+        if content.fingerprint != self.content {
+            return Err("the replay was recorded with other rules, scripts or mods".to_string());
+        }
+        let mut world = self.start.into_world();
+        for &action in &self.actions {
+            if world.player.dead || world.stats.won_at.is_some() {
+                return Err("the game is over before the end of the replay".to_string());
+            }
+            game::play_turn(&mut world, content, action);
+        }
+        Ok(Summary::new(world, self.actions))
+    }
+}
+
+// Today's date as YYYY-MM-DD, in UTC
+pub fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() / 86_400) as i64;
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot;
+    use crate::world::Balance;
+
+    // A game the bot plays from the middle on, so the generator is not where
+    // the seed starts it
+    fn record(content: &Content) -> (World, Summary) {
+        let mut world = World::with_seed(6, Balance::default());
+        for _ in 0..40 {
+            let action = bot::next_action(&world);
+            game::play_turn(&mut world, content, action);
+        }
+        let start = world.clone();
+        let mut actions = Vec::new();
+        while !world.player.dead && world.stats.won_at.is_none() && world.turn < bot::TURN_BUDGET {
+            let action = bot::next_action(&world);
+            game::play_turn(&mut world, content, action);
+            actions.push(action);
+        }
+        (start, Summary::new(world, actions))
+    }
+
+    #[test]
+    fn replays_play_the_game_again() {
+        let content = Content::default();
+        let (start, summary) = record(&content);
+        let replay = Replay::new(&start, &content, &summary.actions);
+        let json = serde_json::to_string(&replay).unwrap();
+        let replay: Replay = serde_json::from_str(&json).unwrap();
+        assert!(replay.play(&content).unwrap() == summary);
+    }
+
+    #[test]
+    fn replays_need_the_content_they_were_recorded_with() {
+        let content = Content::default();
+        let (start, summary) = record(&content);
+        let replay = Replay::new(&start, &content, &summary.actions);
+        let other = Content {
+            fingerprint: 1,
+            ..Content::default()
+        };
+        assert!(replay.play(&other).is_err());
+    }
+
+    fn entry(name: &str, score: u32) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            score,
+            turns: 500,
+            won: true,
+            date: "2024-01-01".to_string(),
+            replay: None,
+        }
+    }
+
+    #[test]
+    fn tables_keep_the_best_scores() {
+        let mut scores = HighScores::default();
+        for score in 1..=TABLE_SIZE as u32 {
+            assert!(scores.qualifies(1, Difficulty::Normal, score));
+            scores.insert(1, Difficulty::Normal, entry("Old", score * 100));
+        }
+        assert!(!scores.qualifies(1, Difficulty::Normal, 100));
+        assert_eq!(
+            scores.insert(1, Difficulty::Normal, entry("Low", 100)),
+            None
+        );
+        // ties go to the earlier score
+        assert_eq!(
+            scores.insert(1, Difficulty::Normal, entry("Tie", 500)),
+            Some(7)
+        );
+        // other seeds and difficulties have tables of their own
+        assert_eq!(
+            scores.insert(1, Difficulty::Hard, entry("Hard", 1)),
+            Some(1)
+        );
+        assert_eq!(
+            scores.insert(2, Difficulty::Normal, entry("Two", 1)),
+            Some(1)
+        );
+        assert_eq!(scores.tables.len(), 3);
+        assert_eq!(scores.tables[0].entries.len(), TABLE_SIZE);
+    }
+
+    #[test]
+    fn scores_survive_a_round_trip() {
+        let path = std::env::temp_dir().join(format!("game-scores-{}.json", std::process::id()));
+        let mut scores = HighScores::default();
+        scores.insert(3, Difficulty::Easy, entry("First", 300));
+        scores.insert(3, Difficulty::Easy, entry("Second", 200));
+        scores.save(&path).unwrap();
+        let loaded = HighScores::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let names: Vec<&str> = loaded.tables[0]
+            .entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(names, ["First", "Second"]);
+        assert!(loaded.tables[0].difficulty == Difficulty::Easy);
+        assert!(HighScores::load(&path).unwrap().tables.is_empty());
+    }
+}
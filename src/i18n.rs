@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+// Languages bundled with the game. Message files live in `locales/` and use a
+// small subset of the Fluent syntax: `message-id = text`, `# comments`, and
+// `{ $name }` placeholders filled from the message arguments.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Language {
+    English,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    // Language every missing message falls back to
+    pub const FALLBACK: Language = Language::English;
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Language> {
+        Language::ALL
+            .into_iter()
+            .find(|language| language.code() == code)
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../locales/en.ftl"),
+            Language::French => include_str!("../locales/fr.ftl"),
+        }
+    }
+}
+
+pub struct Catalog {
+    language: Language,
+    messages: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub fn new(language: Language) -> Catalog {
+        Catalog {
+            language,
+            messages: parse_messages(language.source()),
+            fallback: parse_messages(Language::FALLBACK.source()),
+        }
+    }
+
+    // Add the messages of a mod, see mods.rs
+    pub fn add(&mut self, language: Language, source: &str) {
+        if language == self.language {
+            self.messages.extend(parse_messages(source));
+        }
+        if language == Language::FALLBACK {
+            self.fallback.extend(parse_messages(source));
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    // Look up a message and fill in its placeholders. Messages missing from the
+    // selected language come from the fallback language, unknown ids are shown as is.
+    pub fn message(&self, id: &str, args: &[(&str, String)]) -> String {
+        match self.messages.get(id).or_else(|| self.fallback.get(id)) {
+            Some(pattern) => format_message(pattern, args),
+            None => id.to_string(),
+        }
+    }
+
+    // Ids of the fallback messages this language does not translate, sorted.
+    pub fn missing_messages(&self) -> Vec<&str> {
+        let mut missing: Vec<&str> = self
+            .fallback
+            .keys()
+            .filter(|id| !self.messages.contains_key(*id))
+            .map(String::as_str)
+            .collect();
+        missing.sort_unstable();
+        missing
+    }
+}
+
+fn parse_messages(source: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((id, pattern)) = line.split_once('=') {
+            messages.insert(id.trim().to_string(), pattern.trim().to_string());
+        }
+    }
+    messages
+}
+
+fn format_message(pattern: &str, args: &[(&str, String)]) -> String {
+    let mut message = pattern.to_string();
+    for (name, value) in args {
+        message = message.replace(&format!("{{ ${} }}", name), value);
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_language_translates_every_message() {
+        let mut report = Vec::new();
+        for language in Language::ALL {
+            let catalog = Catalog::new(language);
+            for id in catalog.missing_messages() {
+                report.push(format!("{}: {}", language.code(), id));
+            }
+        }
+        assert!(
+            report.is_empty(),
+            "missing translations:\n{}",
+            report.join("\n")
+        );
+    }
+
+    #[test]
+    fn missing_messages_fall_back() {
+        let mut catalog = Catalog::new(Language::French);
+        catalog.messages.remove("castle-locked");
+        assert_eq!(catalog.missing_messages(), vec!["castle-locked"]);
+        assert_eq!(
+            catalog.message("castle-locked", &[]),
+            "Castle door is locked!"
+        );
+        assert_eq!(catalog.message("no-such-message", &[]), "no-such-message");
+    }
+
+    #[test]
+    fn placeholders_are_filled() {
+        let catalog = Catalog::new(Language::English);
+        assert_eq!(
+            catalog.message("chest-looted", &[("item", "harpoon".to_string())]),
+            "You found a harpoon, but for what ?"
+        );
+    }
+}
@@ -0,0 +1,527 @@
+use crate::i18n::Catalog;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use termion::{clear, cursor, event::Key};
+
+// Default location of the keybindings config, loaded when present
+pub const KEYBINDINGS_FILE: &str = "keybindings.toml";
+
+// Everything the player can ask for from the keyboard
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveNorth,
+    MoveSouth,
+    MoveWest,
+    MoveEast,
+    MoveNorthWest,
+    MoveNorthEast,
+    MoveSouthWest,
+    MoveSouthEast,
+    Wait,
+    MessageLog,
+    Help,
+    Stats,
+    TogglePanel,
+    Save,
+    Quit,
+}
+
+impl Action {
+    // In the order the help screen lists them
+    pub const ALL: [Action; 15] = [
+        Action::MoveNorth,
+        Action::MoveSouth,
+        Action::MoveWest,
+        Action::MoveEast,
+        Action::MoveNorthWest,
+        Action::MoveNorthEast,
+        Action::MoveSouthWest,
+        Action::MoveSouthEast,
+        Action::Wait,
+        Action::MessageLog,
+        Action::Help,
+        Action::Stats,
+        Action::TogglePanel,
+        Action::Save,
+        Action::Quit,
+    ];
+
+    // Movement actions return the (dx, dy) step they take
+    pub fn direction(self) -> Option<(i32, i32)> {
+        match self {
+            Action::MoveNorth => Some((0, -1)),
+            Action::MoveSouth => Some((0, 1)),
+            Action::MoveWest => Some((-1, 0)),
+            Action::MoveEast => Some((1, 0)),
+            Action::MoveNorthWest => Some((-1, -1)),
+            Action::MoveNorthEast => Some((1, -1)),
+            Action::MoveSouthWest => Some((-1, 1)),
+            Action::MoveSouthEast => Some((1, 1)),
+            Action::Wait
+            | Action::MessageLog
+            | Action::Help
+            | Action::Stats
+            | Action::TogglePanel
+            | Action::Save
+            | Action::Quit => None,
+        }
+    }
+
+    // Name of the action in keybindings.toml
+    pub fn config_name(self) -> &'static str {
+        match self {
+            Action::MoveNorth => "move_north",
+            Action::MoveSouth => "move_south",
+            Action::MoveWest => "move_west",
+            Action::MoveEast => "move_east",
+            Action::MoveNorthWest => "move_north_west",
+            Action::MoveNorthEast => "move_north_east",
+            Action::MoveSouthWest => "move_south_west",
+            Action::MoveSouthEast => "move_south_east",
+            Action::Wait => "wait",
+            Action::MessageLog => "message_log",
+            Action::Help => "help",
+            Action::Stats => "stats",
+            Action::TogglePanel => "toggle_panel",
+            Action::Save => "save",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn message_id(self) -> String {
+        format!("action-{}", self.config_name().replace('_', "-"))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Arrows,
+    Wasd,
+    Vi,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::Arrows, Preset::Wasd, Preset::Vi];
+
+    pub fn message_id(self) -> &'static str {
+        match self {
+            Preset::Arrows => "preset-arrows",
+            Preset::Wasd => "preset-wasd",
+            Preset::Vi => "preset-vi",
+        }
+    }
+
+    fn movement_keys(self) -> [(Action, &'static str); 8] {
+        match self {
+            Preset::Arrows => [
+                (Action::MoveNorth, "Up"),
+                (Action::MoveSouth, "Down"),
+                (Action::MoveWest, "Left"),
+                (Action::MoveEast, "Right"),
+                (Action::MoveNorthWest, "Home"),
+                (Action::MoveNorthEast, "PageUp"),
+                (Action::MoveSouthWest, "End"),
+                (Action::MoveSouthEast, "PageDown"),
+            ],
+            Preset::Wasd => [
+                (Action::MoveNorth, "w"),
+                (Action::MoveSouth, "s"),
+                (Action::MoveWest, "a"),
+                (Action::MoveEast, "d"),
+                (Action::MoveNorthWest, "q"),
+                (Action::MoveNorthEast, "e"),
+                (Action::MoveSouthWest, "z"),
+                (Action::MoveSouthEast, "c"),
+            ],
+            Preset::Vi => [
+                (Action::MoveNorth, "k"),
+                (Action::MoveSouth, "j"),
+                (Action::MoveWest, "h"),
+                (Action::MoveEast, "l"),
+                (Action::MoveNorthWest, "y"),
+                (Action::MoveNorthEast, "u"),
+                (Action::MoveSouthWest, "b"),
+                (Action::MoveSouthEast, "n"),
+            ],
+        }
+    }
+}
+
+// keybindings.toml: pick a preset (the one from the settings when left out),
+// then override the keys of any action
+//
+//   preset = "vi"
+//
+//   [bindings]
+//   wait = [".", "Space"]
+//   message_log = ["m"]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyBindingsFile {
+    preset: Option<Preset>,
+    #[serde(default)]
+    bindings: HashMap<Action, Vec<String>>,
+}
+
+pub struct KeyBindings {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings::preset(Preset::default())
+    }
+}
+
+impl KeyBindings {
+    pub fn preset(preset: Preset) -> KeyBindings {
+        let mut bindings: Vec<(Key, Action)> = preset
+            .movement_keys()
+            .into_iter()
+            .map(|(action, key)| (parse_key(key).unwrap(), action))
+            .collect();
+        bindings.extend([
+            (Key::Char('.'), Action::Wait),
+            (Key::Char(' '), Action::Wait),
+            (Key::Char('m'), Action::MessageLog),
+            (Key::Char('?'), Action::Help),
+            (Key::Char('t'), Action::Stats),
+            (Key::Char('\t'), Action::TogglePanel),
+            (Key::F(5), Action::Save),
+            (Key::Ctrl('s'), Action::Save),
+            (Key::Esc, Action::Quit),
+        ]);
+        KeyBindings { bindings }
+    }
+
+    // Load the keybindings config, or the given preset when there is no such file.
+    pub fn load(path: &Path, preset: Preset) -> Result<KeyBindings, String> {
+        if !path.exists() {
+            return Ok(KeyBindings::preset(preset));
+        }
+        let content = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        KeyBindings::parse(&content, preset)
+            .map_err(|message| format!("{}: {}", path.display(), message))
+    }
+
+    pub fn parse(content: &str, preset: Preset) -> Result<KeyBindings, String> {
+        let file: KeyBindingsFile = toml::from_str(content).map_err(|error| error.to_string())?;
+        let mut keybindings = KeyBindings::preset(file.preset.unwrap_or(preset));
+
+        // overridden actions lose their preset keys, then the overrides are added
+        // in a fixed order so errors do not depend on the hash map order
+        keybindings
+            .bindings
+            .retain(|(_, action)| !file.bindings.contains_key(action));
+        for action in Action::ALL {
+            let Some(keys) = file.bindings.get(&action) else {
+                continue;
+            };
+            for name in keys {
+                let key = parse_key(name)?;
+                match keybindings.action_for(key) {
+                    Some(other) if other == action => {
+                        return Err(format!(
+                            "key '{}' is listed twice for {}",
+                            name,
+                            action.config_name()
+                        ))
+                    }
+                    Some(other) => {
+                        return Err(format!(
+                            "key '{}' is bound to both {} and {}",
+                            name,
+                            other.config_name(),
+                            action.config_name()
+                        ))
+                    }
+                    None => keybindings.bindings.push((key, action)),
+                }
+            }
+        }
+
+        Ok(keybindings)
+    }
+
+    pub fn action_for(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn keys_for(&self, action: Action) -> impl Iterator<Item = Key> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+    }
+}
+
+// Key names used in the config: a single character, "Ctrl-x", "Alt-x", "F1".."F12",
+// or one of the named keys below.
+fn parse_key(name: &str) -> Result<Key, String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(Key::Char(c));
+    }
+    let single_char = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    let key = match name {
+        "Up" => Key::Up,
+        "Down" => Key::Down,
+        "Left" => Key::Left,
+        "Right" => Key::Right,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "PageUp" => Key::PageUp,
+        "PageDown" => Key::PageDown,
+        "Insert" => Key::Insert,
+        "Delete" => Key::Delete,
+        "Backspace" => Key::Backspace,
+        "Enter" => Key::Char('\n'),
+        "Tab" => Key::Char('\t'),
+        "Space" => Key::Char(' '),
+        "Esc" => Key::Esc,
+        _ => {
+            if let Some(c) = name.strip_prefix("Ctrl-").and_then(single_char) {
+                Key::Ctrl(c)
+            } else if let Some(c) = name.strip_prefix("Alt-").and_then(single_char) {
+                Key::Alt(c)
+            } else if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse().ok()) {
+                Key::F(n)
+            } else {
+                return Err(format!("unknown key '{}'", name));
+            }
+        }
+    };
+    Ok(key)
+}
+
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Up => "Up".to_string(),
+        Key::Down => "Down".to_string(),
+        Key::Left => "Left".to_string(),
+        Key::Right => "Right".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "PageUp".to_string(),
+        Key::PageDown => "PageDown".to_string(),
+        Key::Insert => "Insert".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Backspace => "Backspace".to_string(),
+        Key::Char('\n') => "Enter".to_string(),
+        Key::Char('\t') => "Tab".to_string(),
+        Key::Char(' ') => "Space".to_string(),
+        Key::Esc => "Esc".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{}", c),
+        Key::Alt(c) => format!("Alt-{}", c),
+        Key::F(n) => format!("F{}", n),
+        _ => "?".to_string(),
+    }
+}
+
+// Full screen list of the current bindings, closed by any key.
+pub fn show_help<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+    stdout: &mut W,
+    keys: &mut I,
+    keybindings: &KeyBindings,
+    catalog: &Catalog,
+) -> Result<(), io::Error> {
+    let mut frame = format!(
+        "\x1b[1m\x1b[93m{}\x1b[0m\r\n\r\n",
+        catalog.message("help-title", &[])
+    );
+    for action in Action::ALL {
+        let bound: Vec<String> = keybindings.keys_for(action).map(key_name).collect();
+        frame.push_str(&format!(
+            "  {:<28} {}\r\n",
+            catalog.message(&action.message_id(), &[]),
+            bound.join(", ")
+        ));
+    }
+    frame.push_str(&format!("\r\n{}\r\n", catalog.message("help-close", &[])));
+
+    write!(stdout, "{}{}{}", clear::All, cursor::Goto(1, 1), frame)?;
+    stdout.flush()?;
+
+    if let Some(key_event) = keys.next() {
+        key_event?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Case {
+        name: &'static str,
+        config: &'static str,
+        // keys and the action they trigger once loaded, none when unbound
+        bound: &'static [(&'static str, Option<Action>)],
+        error: Option<&'static str>,
+    }
+
+    const CASES: &[Case] = &[
+        Case {
+            name: "empty file keeps the preset",
+            config: "",
+            bound: &[
+                ("Up", Some(Action::MoveNorth)),
+                ("k", None),
+                (".", Some(Action::Wait)),
+            ],
+            error: None,
+        },
+        Case {
+            name: "vi preset",
+            config: "preset = \"vi\"",
+            bound: &[
+                ("k", Some(Action::MoveNorth)),
+                ("n", Some(Action::MoveSouthEast)),
+                ("Up", None),
+                ("Esc", Some(Action::Quit)),
+            ],
+            error: None,
+        },
+        Case {
+            name: "wasd preset",
+            config: "preset = \"wasd\"",
+            bound: &[
+                ("w", Some(Action::MoveNorth)),
+                ("c", Some(Action::MoveSouthEast)),
+                ("Down", None),
+                ("t", Some(Action::Stats)),
+            ],
+            error: None,
+        },
+        Case {
+            name: "overrides replace the preset keys",
+            config: "[bindings]\nwait = [\"x\"]\nmessage_log = [\"Ctrl-l\", \"F2\"]",
+            bound: &[
+                ("x", Some(Action::Wait)),
+                (".", None),
+                ("Space", None),
+                ("Ctrl-l", Some(Action::MessageLog)),
+                ("F2", Some(Action::MessageLog)),
+                ("m", None),
+            ],
+            error: None,
+        },
+        Case {
+            name: "keys freed by an override can be bound again",
+            config: "[bindings]\nmessage_log = [\"l\"]\nwait = [\"m\"]",
+            bound: &[("m", Some(Action::Wait)), ("l", Some(Action::MessageLog))],
+            error: None,
+        },
+        Case {
+            name: "override on top of another preset",
+            config: "preset = \"vi\"\n[bindings]\nmove_north = [\"Up\"]",
+            bound: &[("Up", Some(Action::MoveNorth)), ("k", None)],
+            error: None,
+        },
+        Case {
+            name: "key of another action in the preset",
+            config: "[bindings]\nwait = [\"m\"]",
+            bound: &[],
+            error: Some("key 'm' is bound to both message_log and wait"),
+        },
+        Case {
+            name: "key of another override",
+            config: "[bindings]\nwait = [\"x\"]\nsave = [\"x\"]",
+            bound: &[],
+            error: Some("key 'x' is bound to both wait and save"),
+        },
+        Case {
+            name: "key of a movement of the chosen preset",
+            config: "preset = \"wasd\"\n[bindings]\nhelp = [\"w\"]",
+            bound: &[],
+            error: Some("key 'w' is bound to both move_north and help"),
+        },
+        Case {
+            name: "same key twice for an action",
+            config: "[bindings]\nwait = [\"x\", \"x\"]",
+            bound: &[],
+            error: Some("key 'x' is listed twice for wait"),
+        },
+        Case {
+            name: "unknown key",
+            config: "[bindings]\nwait = [\"Hyper-x\"]",
+            bound: &[],
+            error: Some("unknown key 'Hyper-x'"),
+        },
+        Case {
+            name: "unknown action",
+            config: "[bindings]\nfly = [\"f\"]",
+            bound: &[],
+            error: Some("fly"),
+        },
+    ];
+
+    #[test]
+    fn configs_load() {
+        for case in CASES {
+            match (KeyBindings::parse(case.config, Preset::Arrows), case.error) {
+                (Ok(keybindings), None) => {
+                    for &(name, action) in case.bound {
+                        let bound = keybindings.action_for(parse_key(name).unwrap());
+                        assert!(bound == action, "{}: key {}", case.name, name);
+                    }
+                }
+                (Err(message), Some(error)) => {
+                    assert!(message.contains(error), "{}: {}", case.name, message)
+                }
+                (Ok(_), Some(error)) => panic!("{}: expected {}", case.name, error),
+                (Err(message), None) => panic!("{}: {}", case.name, message),
+            }
+        }
+    }
+
+    #[test]
+    fn presets_bind_every_action_once() {
+        for preset in Preset::ALL {
+            let keybindings = KeyBindings::preset(preset);
+            for action in Action::ALL {
+                assert!(
+                    keybindings.keys_for(action).count() > 0,
+                    "{}: {}",
+                    preset.message_id(),
+                    action.config_name()
+                );
+            }
+            for (index, (key, _)) in keybindings.bindings.iter().enumerate() {
+                assert!(
+                    keybindings.bindings[index + 1..]
+                        .iter()
+                        .all(|(other, _)| other != key),
+                    "{}: {} bound twice",
+                    preset.message_id(),
+                    key_name(*key)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        for name in [
+            "a", "Space", "Enter", "Tab", "Ctrl-s", "Alt-x", "F12", "PageDown",
+        ] {
+            assert_eq!(key_name(parse_key(name).unwrap()), name);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+pub mod bot;
+pub mod dev;
+pub mod editor;
+pub mod events;
+pub mod game;
+pub mod gym;
+pub mod highscores;
+pub mod i18n;
+pub mod keybindings;
+pub mod menu;
+pub mod message_log;
+pub mod mods;
+pub mod outcome;
+pub mod panel;
+pub mod profile;
+pub mod protocol;
+pub mod scheduler;
+pub mod scripting;
+pub mod settings;
+pub mod simulate;
+pub mod speedrun;
+pub mod tileset;
+pub mod world;
@@ -1,14 +1,303 @@
-pub mod game;
-pub mod world;
-use std::io::{self};
-use termion::{raw::IntoRawMode, screen::AlternateScreen};
+use ascii_rpg::{
+    bot, dev, editor, game, highscores, i18n, keybindings, menu, mods, profile, protocol,
+    scripting, settings, simulate, world,
+};
+use game::{Content, Setup};
+use highscores::Replay;
+use i18n::{Catalog, Language};
+use keybindings::{KeyBindings, KEYBINDINGS_FILE};
+use menu::{ConfigFiles, Menu};
+use mods::MODS_DIR;
+use profile::{Profile, PROFILE_FILE};
+use scripting::SCRIPTS_DIR;
+use settings::{Settings, SETTINGS_FILE};
+use simulate::Policy;
+use std::env;
+use std::io::{self, Stdout};
+use std::path::{Path, PathBuf};
+use termion::raw::{IntoRawMode, RawTerminal};
+use termion::{async_stdin, input::TermRead, screen::AlternateScreen};
+use world::balance::BALANCE_FILE;
+use world::rules::RULES_FILE;
+use world::{Balance, Difficulty, Map, World};
+
+const USAGE: &str = "usage: game [--lang <en|fr>] [--keys <file>] [--difficulty <easy|normal|hard>] [--balance <file>] [--rules <file>] [--scripts <dir>] [--mods <dir>] [--dev] [--stdio-protocol] [--protocol-schema] [--bot] [--simulate <runs>] [--policy <solver|random>] [--summary] [--replay <file>] [--seed <number>] [--map <file>] [--load <file>] [--edit <file>]";
+
+enum Mode {
+    // the main menu, after playing the world given on the command line if any
+    Menu(Option<Box<World>>),
+    Edit(PathBuf),
+    // JSON lines on stdin and stdout for bots, nothing drawn
+    Protocol(Box<World>),
+    // the built-in bot playing, for the player to watch
+    Bot(Box<World>),
+    // that many games played headless from the seed on, written as CSV
+    Simulate(u64, u64),
+    // a recorded game played again headless, with how it ended written out
+    Replay(PathBuf),
+}
+
+struct Options {
+    map: Option<PathBuf>,
+    load: Option<PathBuf>,
+    seed: Option<u64>,
+    edit: Option<PathBuf>,
+    replay: Option<PathBuf>,
+    keys: PathBuf,
+    // overrides the difficulty from the settings
+    difficulty: Option<Difficulty>,
+    balance: PathBuf,
+    rules: PathBuf,
+    scripts: PathBuf,
+    mods: PathBuf,
+    dev: bool,
+    stdio_protocol: bool,
+    bot: bool,
+    simulate: Option<u64>,
+    policy: Policy,
+    // the distributions of the simulation instead of its games
+    summary: bool,
+    // print the schema of the protocol and exit
+    protocol_schema: bool,
+    language: Language,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        map: None,
+        load: None,
+        seed: None,
+        edit: None,
+        replay: None,
+        keys: PathBuf::from(KEYBINDINGS_FILE),
+        difficulty: None,
+        balance: PathBuf::from(BALANCE_FILE),
+        rules: PathBuf::from(RULES_FILE),
+        scripts: PathBuf::from(SCRIPTS_DIR),
+        mods: PathBuf::from(MODS_DIR),
+        dev: false,
+        stdio_protocol: false,
+        bot: false,
+        simulate: None,
+        policy: Policy::Solver,
+        summary: false,
+        protocol_schema: false,
+        language: Language::English,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--map" => options.map = Some(PathBuf::from(value()?)),
+            "--load" => options.load = Some(PathBuf::from(value()?)),
+            "--seed" => {
+                let seed = value()?;
+                options.seed = Some(
+                    seed.parse()
+                        .map_err(|_| format!("invalid seed '{}'", seed))?,
+                );
+            }
+            "--edit" => options.edit = Some(PathBuf::from(value()?)),
+            "--keys" => options.keys = PathBuf::from(value()?),
+            "--difficulty" => {
+                let id = value()?;
+                options.difficulty =
+                    Some(Difficulty::from_id(id).ok_or(format!("unknown difficulty '{}'", id))?);
+            }
+            "--balance" => options.balance = PathBuf::from(value()?),
+            "--rules" => options.rules = PathBuf::from(value()?),
+            "--scripts" => options.scripts = PathBuf::from(value()?),
+            "--mods" => options.mods = PathBuf::from(value()?),
+            "--dev" => options.dev = true,
+            "--stdio-protocol" => options.stdio_protocol = true,
+            "--bot" => options.bot = true,
+            "--simulate" => {
+                let runs = value()?;
+                options.simulate = Some(
+                    runs.parse()
+                        .map_err(|_| format!("invalid number of runs '{}'", runs))?,
+                );
+            }
+            "--policy" => {
+                let id = value()?;
+                options.policy = Policy::from_id(id).ok_or(format!("unknown policy '{}'", id))?;
+            }
+            "--summary" => options.summary = true,
+            "--replay" => options.replay = Some(PathBuf::from(value()?)),
+            "--protocol-schema" => options.protocol_schema = true,
+            "--lang" => {
+                let code = value()?;
+                options.language =
+                    Language::from_code(code).ok_or(format!("unknown language '{}'", code))?;
+            }
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+    if let Some(runs) = options.simulate {
+        let first_seed = options.seed.unwrap_or(0);
+        if first_seed.checked_add(runs).is_none() {
+            return Err(format!(
+                "{} runs from seed {} go past the last seed",
+                runs, first_seed
+            ));
+        }
+    }
+    Ok(options)
+}
 
 fn main() -> Result<(), io::Error> {
-    let stdout = io::stdout().into_raw_mode()?;
-    let mut stdout = AlternateScreen::from(stdout);
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+    if options.protocol_schema {
+        println!("{}", protocol::schema());
+        return Ok(());
+    }
+    let files = ConfigFiles {
+        keybindings: options.keys,
+        balance: options.balance,
+        rules: options.rules,
+        scripts: options.scripts,
+        mods: options.mods,
+        dev: options.dev,
+    };
+    // first, everything else may use the types the mods add
+    let mut catalog = Catalog::new(options.language);
+    let content = match Content::load(&files, &mut catalog) {
+        Ok(content) => content,
+        Err(message) => {
+            eprintln!("invalid {}", message);
+            std::process::exit(2);
+        }
+    };
+    // still on screen once the game is over
+    for conflict in &content.conflicts {
+        eprintln!("mod conflict: {}", conflict);
+    }
+    let mut settings = match Settings::load(Path::new(SETTINGS_FILE)) {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("invalid settings: {}", message);
+            std::process::exit(2);
+        }
+    };
+    let keybindings = match KeyBindings::load(&files.keybindings, settings.keybindings) {
+        Ok(keybindings) => keybindings,
+        Err(message) => {
+            eprintln!("invalid keybindings: {}", message);
+            std::process::exit(2);
+        }
+    };
+    if let Some(difficulty) = options.difficulty {
+        settings.difficulty = difficulty;
+    }
+    let balance = match Balance::load(&files.balance, settings.difficulty) {
+        Ok(balance) => balance,
+        Err(message) => {
+            eprintln!("invalid balance: {}", message);
+            std::process::exit(2);
+        }
+    };
+    let profile = match Profile::load(Path::new(PROFILE_FILE)) {
+        Ok(profile) => profile,
+        Err(error) => {
+            eprintln!("invalid profile: {}", error);
+            std::process::exit(2);
+        }
+    };
+    let mode = match (options.edit, options.simulate, options.replay) {
+        (Some(path), _, _) => Mode::Edit(path),
+        (None, Some(runs), _) => Mode::Simulate(options.seed.unwrap_or(0), runs),
+        (None, None, Some(path)) => Mode::Replay(path),
+        (None, None, None) => {
+            let world = match (options.load, options.map, options.seed) {
+                (Some(path), _, _) => Some(World::load(&path)?),
+                (None, Some(path), _) => Some(World::from_map(Map::load(&path)?, balance)),
+                (None, None, Some(seed)) => Some(World::with_seed(seed, balance)),
+                (None, None, None) => None,
+            };
+            // bots play a world of their own when none is given
+            let bot_world = || Box::new(World::with_seed(rand::random(), balance));
+            match world.map(Box::new) {
+                world if options.stdio_protocol => Mode::Protocol(world.unwrap_or_else(bot_world)),
+                world if options.bot => Mode::Bot(world.unwrap_or_else(bot_world)),
+                world => Mode::Menu(world),
+            }
+        }
+    };
 
-    // start game loop
-    game::start(&mut stdout)?;
+    // the headless modes write to stdout as it is, the others take the screen
+    match mode {
+        Mode::Protocol(mut world) => {
+            protocol::run(&mut world, &content, io::stdin().lock(), &mut io::stdout())?
+        }
+        Mode::Simulate(first_seed, runs) => {
+            let runs: Vec<_> = (first_seed..first_seed + runs)
+                .map(|seed| {
+                    simulate::simulate(seed, options.policy, balance, &content, bot::TURN_BUDGET)
+                })
+                .collect();
+            if options.summary {
+                simulate::write_summary(&mut io::stdout(), &runs)?
+            } else {
+                simulate::write_runs(&mut io::stdout(), &runs)?
+            }
+        }
+        Mode::Replay(path) => {
+            let summary = Replay::read(&path)?
+                .play(&content)
+                .unwrap_or_else(|message| {
+                    eprintln!("{}: {}", path.display(), message);
+                    std::process::exit(1);
+                });
+            println!(
+                "{}: {} in {} turns, score {}",
+                summary.name,
+                if summary.won { "won" } else { "died" },
+                summary.turns,
+                highscores::score(&summary)
+            );
+        }
+        Mode::Menu(world) => {
+            let mut stdout = screen()?;
+            let world = world.map(|world| *world);
+            let dev = files.dev.then(|| dev::keys(&files));
+            let mut menu = Menu::new(
+                settings,
+                keybindings,
+                balance,
+                content,
+                profile,
+                files,
+                &catalog,
+            );
+            match dev {
+                Some(mut keys) => menu.run(&mut stdout, &mut keys, world)?,
+                None => menu.run(&mut stdout, &mut dev::inputs(io::stdin().keys()), world)?,
+            }
+        }
+        Mode::Edit(path) => editor::start(&mut screen()?, &path)?,
+        Mode::Bot(world) => {
+            let setup = Setup {
+                settings: &settings,
+                keybindings: &keybindings,
+                content: &content,
+                catalog: &catalog,
+                files: &files,
+            };
+            game::watch(&mut screen()?, &mut async_stdin().keys(), *world, &setup)?
+        }
+    }
 
     Ok(())
 }
+
+// The terminal in raw mode on the alternate screen, for the modes drawing the game
+fn screen() -> Result<AlternateScreen<RawTerminal<Stdout>>, io::Error> {
+    Ok(AlternateScreen::from(io::stdout().into_raw_mode()?))
+}
@@ -0,0 +1,636 @@
+use crate::dev::{Input, KeysOnly, Reloaded, Watch};
+use crate::game::{self, Content, Setup, Summary};
+use crate::highscores::{self, HighScore, HighScores, Replay, HIGHSCORES_FILE};
+use crate::i18n::Catalog;
+use crate::keybindings::{self, KeyBindings, Preset};
+use crate::message_log;
+use crate::outcome;
+use crate::profile::{Profile, PROFILE_FILE};
+use crate::settings::{Settings, SETTINGS_FILE};
+use crate::tileset::Tileset;
+use crate::world::save::SAVE_FILE;
+use crate::world::{Balance, Difficulty, Map, World};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use termion::{clear, cursor, event::Key};
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Entry {
+    NewGame,
+    Continue,
+    Load,
+    Settings,
+    Controls,
+    HighScores,
+    Quit,
+}
+
+impl Entry {
+    const ALL: [Entry; 7] = [
+        Entry::NewGame,
+        Entry::Continue,
+        Entry::Load,
+        Entry::Settings,
+        Entry::Controls,
+        Entry::HighScores,
+        Entry::Quit,
+    ];
+
+    fn message_id(self) -> &'static str {
+        match self {
+            Entry::NewGame => "menu-new-game",
+            Entry::Continue => "menu-continue",
+            Entry::Load => "menu-load",
+            Entry::Settings => "menu-settings",
+            Entry::Controls => "menu-controls",
+            Entry::HighScores => "menu-high-scores",
+            Entry::Quit => "menu-quit",
+        }
+    }
+}
+
+// What to do once a game is over
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum EndChoice {
+    Restart,
+    NewSeed,
+    MainMenu,
+    Quit,
+}
+
+impl EndChoice {
+    const ALL: [EndChoice; 4] = [
+        EndChoice::Restart,
+        EndChoice::NewSeed,
+        EndChoice::MainMenu,
+        EndChoice::Quit,
+    ];
+
+    fn message_id(self) -> &'static str {
+        match self {
+            EndChoice::Restart => "end-restart",
+            EndChoice::NewSeed => "end-new-seed",
+            EndChoice::MainMenu => "end-main-menu",
+            EndChoice::Quit => "end-quit",
+        }
+    }
+}
+
+// Rows of the settings screen
+const SETTING_ROWS: usize = 5;
+
+// Config files loaded again when the settings they depend on change, or
+// when they change themselves with --dev
+pub struct ConfigFiles {
+    // with the keybindings preset
+    pub keybindings: PathBuf,
+    // with the difficulty
+    pub balance: PathBuf,
+    pub rules: PathBuf,
+    pub scripts: PathBuf,
+    pub mods: PathBuf,
+    // watch the files and reload them while the game runs
+    pub dev: bool,
+}
+
+pub struct Menu<'a> {
+    settings: Settings,
+    keybindings: KeyBindings,
+    balance: Balance,
+    content: Content,
+    profile: Profile,
+    files: ConfigFiles,
+    catalog: &'a Catalog,
+    // with --dev, the files reloaded before the next game and the messages
+    // of the mods reloaded with them
+    watch: Option<Watch>,
+    dev_catalog: Option<Catalog>,
+    selected: usize,
+    // shown under the entries, e.g. why a game could not be loaded
+    status: String,
+    // set when the player chose to quit from an end screen
+    quitting: bool,
+}
+
+impl<'a> Menu<'a> {
+    pub fn new(
+        settings: Settings,
+        keybindings: KeyBindings,
+        balance: Balance,
+        content: Content,
+        profile: Profile,
+        files: ConfigFiles,
+        catalog: &'a Catalog,
+    ) -> Menu<'a> {
+        Menu {
+            settings,
+            keybindings,
+            balance,
+            content,
+            profile,
+            watch: files.dev.then(|| Watch::new(&files)),
+            files,
+            catalog,
+            dev_catalog: None,
+            selected: 0,
+            status: String::new(),
+            quitting: false,
+        }
+    }
+
+    // Show the main menu until the player quits. A world given on the command
+    // line is played first, the menu comes back once the game is over.
+    pub fn run<W: Write, I: Iterator<Item = Result<Input, io::Error>>>(
+        &mut self,
+        stdout: &mut W,
+        keys: &mut I,
+        world: Option<World>,
+    ) -> Result<(), io::Error> {
+        if let Some(world) = world {
+            self.play(stdout, keys, world)?;
+        }
+
+        while !self.quitting {
+            self.render(stdout)?;
+            let Some(key_event) = keys.next() else {
+                return Ok(());
+            };
+            let key = match key_event? {
+                Input::Key(key) => key,
+                Input::Reload => {
+                    self.reload();
+                    continue;
+                }
+            };
+            match key {
+                Key::Up => {
+                    self.selected = (self.selected + Entry::ALL.len() - 1) % Entry::ALL.len()
+                }
+                Key::Down => self.selected = (self.selected + 1) % Entry::ALL.len(),
+                Key::Esc | Key::Char('q') => return Ok(()),
+                Key::Char('\n') => {
+                    self.status.clear();
+                    match Entry::ALL[self.selected] {
+                        Entry::NewGame => {
+                            let world = World::with_seed(rand::random(), self.balance);
+                            self.play(stdout, keys, world)?;
+                        }
+                        Entry::Continue => {
+                            if Path::new(SAVE_FILE).exists() {
+                                self.load(stdout, keys, Path::new(SAVE_FILE))?;
+                            } else {
+                                self.status = self.catalog.message("menu-no-save", &[]);
+                            }
+                        }
+                        Entry::Load => {
+                            let prompt = self.catalog.message("menu-load-prompt", &[]);
+                            let path = message_log::read_query(
+                                stdout,
+                                &mut KeysOnly(keys),
+                                &self.frame(),
+                                &prompt,
+                            )?;
+                            if !path.is_empty() {
+                                self.load(stdout, keys, Path::new(&path))?;
+                            }
+                        }
+                        Entry::Settings => self.edit_settings(stdout, &mut KeysOnly(keys))?,
+                        Entry::Controls => keybindings::show_help(
+                            stdout,
+                            &mut KeysOnly(keys),
+                            &self.keybindings,
+                            self.catalog,
+                        )?,
+                        Entry::HighScores => match HighScores::load(Path::new(HIGHSCORES_FILE)) {
+                            Ok(scores) => scores.show(stdout, &mut KeysOnly(keys), self.catalog)?,
+                            Err(error) => {
+                                self.status = self
+                                    .catalog
+                                    .message("scores-load-failed", &[("error", error.to_string())])
+                            }
+                        },
+                        Entry::Quit => return Ok(()),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    // Play the world, and from the end screen play it again or another one as
+    // long as the player wants to
+    fn play<W: Write, I: Iterator<Item = Result<Input, io::Error>>>(
+        &mut self,
+        stdout: &mut W,
+        keys: &mut I,
+        mut world: World,
+    ) -> Result<(), io::Error> {
+        loop {
+            // a new game is played under the player's name, loaded ones keep theirs
+            if world.turn == 0 {
+                if self.settings.player_name.is_empty() {
+                    self.ask_name(stdout, &mut KeysOnly(keys))?;
+                }
+                world.player.name = self.settings.player_name.clone();
+            }
+            // restarting plays the world again from the very same start
+            let start = world.clone();
+            self.reload();
+            let setup = Setup {
+                settings: &self.settings,
+                keybindings: &self.keybindings,
+                content: &self.content,
+                catalog: self.dev_catalog.as_ref().unwrap_or(self.catalog),
+                files: &self.files,
+            };
+            let summary = game::start(stdout, keys, world, &setup, &mut self.profile)?;
+            // achievements may have been unlocked even if the game is not over
+            if let Some(summary) = &summary {
+                self.profile.lifetime.add(&summary.stats);
+            }
+            if let Err(error) = self.profile.save(Path::new(PROFILE_FILE)) {
+                self.status = self
+                    .catalog
+                    .message("profile-save-failed", &[("error", error.to_string())]);
+            }
+            let Some(summary) = summary else {
+                return Ok(()); // The player quit the game
+            };
+            let rank = self.record_score(&start, &summary);
+            world = match self.end_screen(stdout, &mut KeysOnly(keys), &summary, rank)? {
+                EndChoice::Restart => start,
+                EndChoice::NewSeed => World::with_seed(rand::random(), self.balance),
+                EndChoice::MainMenu => return Ok(()),
+                EndChoice::Quit => {
+                    self.quitting = true;
+                    return Ok(());
+                }
+            };
+        }
+    }
+
+    // With --dev, load again the files changed since the last time
+    fn reload(&mut self) {
+        let Some(watch) = &mut self.watch else {
+            return;
+        };
+        let results = watch.reload(
+            &self.files,
+            self.settings.difficulty,
+            self.catalog.language(),
+        );
+        for result in results {
+            self.status = match result {
+                Ok(reload) => {
+                    let message_id = reload.message_id();
+                    match reload {
+                        Reloaded::Balance(balance) => self.balance = balance,
+                        Reloaded::Content(content, catalog) => {
+                            self.content = *content;
+                            self.dev_catalog = Some(catalog);
+                        }
+                        Reloaded::Theme(tileset) => self.settings.tileset = tileset,
+                    }
+                    self.catalog.message(message_id, &[])
+                }
+                Err(error) => self
+                    .catalog
+                    .message("dev-reload-failed", &[("error", error)]),
+            };
+        }
+    }
+
+    fn ask_name<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+        &mut self,
+        stdout: &mut W,
+        keys: &mut I,
+    ) -> Result<(), io::Error> {
+        let frame = format!(
+            "\x1b[1m\x1b[93m{}\x1b[0m\r\n\r\n",
+            self.catalog.message("name-title", &[])
+        );
+        let prompt = self.catalog.message("name-prompt", &[]);
+        let name = message_log::read_query(stdout, keys, &frame, &prompt)?;
+        self.settings.player_name = match name.trim() {
+            "" => self.catalog.message("name-default", &[]),
+            name => name.to_string(),
+        };
+        if let Err(error) = self.settings.save(Path::new(SETTINGS_FILE)) {
+            self.status = self
+                .catalog
+                .message("settings-save-failed", &[("error", error.to_string())]);
+        }
+        Ok(())
+    }
+
+    // Enter the game in the high-score table, with a replay when it makes it.
+    // Returns its rank in the table.
+    fn record_score(&mut self, start: &World, summary: &Summary) -> Option<usize> {
+        let score = highscores::score(summary);
+        let mut scores = match HighScores::load(Path::new(HIGHSCORES_FILE)) {
+            Ok(scores) => scores,
+            Err(error) => {
+                self.status = self
+                    .catalog
+                    .message("scores-load-failed", &[("error", error.to_string())]);
+                return None;
+            }
+        };
+        if !scores.qualifies(summary.seed, summary.difficulty, score) {
+            return None;
+        }
+
+        let replay = match Replay::new(start, &self.content, &summary.actions).write() {
+            Ok(path) => Some(path),
+            Err(error) => {
+                self.status = self
+                    .catalog
+                    .message("replay-save-failed", &[("error", error.to_string())]);
+                None
+            }
+        };
+        let entry = HighScore {
+            name: summary.name.clone(),
+            score,
+            turns: summary.turns,
+            won: summary.won,
+            date: highscores::today(),
+            replay,
+        };
+        let rank = scores.insert(summary.seed, summary.difficulty, entry);
+        if let Err(error) = scores.save(Path::new(HIGHSCORES_FILE)) {
+            self.status = self
+                .catalog
+                .message("scores-save-failed", &[("error", error.to_string())]);
+        }
+        rank
+    }
+
+    fn end_screen<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+        &self,
+        stdout: &mut W,
+        keys: &mut I,
+        summary: &Summary,
+        rank: Option<usize>,
+    ) -> Result<EndChoice, io::Error> {
+        let catalog = self.catalog;
+        let title = if summary.won {
+            format!(
+                "\x1b[1m\x1b[92m{}\x1b[0m",
+                catalog.message("end-victory", &[])
+            )
+        } else {
+            format!(
+                "\x1b[1m\x1b[91m{}\x1b[0m",
+                catalog.message("end-defeat", &[])
+            )
+        };
+        let items: Vec<String> = summary
+            .items
+            .iter()
+            .map(|&item| {
+                format!(
+                    "{} {}",
+                    self.settings.tileset.item(item),
+                    outcome::item_name(item, catalog)
+                )
+            })
+            .collect();
+        let mut report = vec![
+            catalog.message("end-seed", &[("seed", summary.seed.to_string())]),
+            catalog.message("end-turns", &[("turns", summary.turns.to_string())]),
+            catalog.message("end-items", &[("items", items.join(", "))]),
+            catalog.message("end-kills", &[("kills", summary.stats.kills().to_string())]),
+            catalog.message(
+                "end-score",
+                &[("score", highscores::score(summary).to_string())],
+            ),
+        ];
+        if let Some(rank) = rank {
+            report.push(catalog.message("end-high-score", &[("rank", rank.to_string())]));
+        }
+
+        let mut selected = 0;
+        loop {
+            let mut frame = format!("{}\r\n\r\n", title);
+            for line in &report {
+                frame.push_str(&format!("  {}\r\n", line));
+            }
+            frame.push_str("\r\n");
+            for (index, choice) in EndChoice::ALL.into_iter().enumerate() {
+                frame.push_str(&entry_line(
+                    &catalog.message(choice.message_id(), &[]),
+                    index == selected,
+                ));
+            }
+            frame.push_str(&format!("\r\n{}", catalog.message("end-help", &[])));
+            write!(stdout, "{}{}{}", clear::All, cursor::Goto(1, 1), frame)?;
+            stdout.flush()?;
+
+            match keys.next() {
+                Some(Ok(Key::Up)) => {
+                    selected = (selected + EndChoice::ALL.len() - 1) % EndChoice::ALL.len()
+                }
+                Some(Ok(Key::Down)) => selected = (selected + 1) % EndChoice::ALL.len(),
+                Some(Ok(Key::Char('\n'))) => return Ok(EndChoice::ALL[selected]),
+                Some(Ok(Key::Esc)) => return Ok(EndChoice::MainMenu),
+                Some(Ok(_)) => {}
+                Some(Err(error)) => return Err(error),
+                None => return Ok(EndChoice::Quit),
+            }
+        }
+    }
+
+    // Play a saved game, or a hand-made map for any file that is not a save
+    fn load<W: Write, I: Iterator<Item = Result<Input, io::Error>>>(
+        &mut self,
+        stdout: &mut W,
+        keys: &mut I,
+        path: &Path,
+    ) -> Result<(), io::Error> {
+        let world = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            World::load(path)
+        } else {
+            Map::load(path).map(|map| World::from_map(map, self.balance))
+        };
+        match world {
+            Ok(world) => self.play(stdout, keys, world),
+            Err(error) => {
+                self.status = self.catalog.message(
+                    "menu-load-failed",
+                    &[
+                        ("path", path.display().to_string()),
+                        ("error", error.to_string()),
+                    ],
+                );
+                Ok(())
+            }
+        }
+    }
+
+    fn edit_settings<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+        &mut self,
+        stdout: &mut W,
+        keys: &mut I,
+    ) -> Result<(), io::Error> {
+        let mut row = 0;
+        loop {
+            self.render_settings(stdout, row)?;
+            let step = match keys.next() {
+                Some(Ok(Key::Up)) => {
+                    row = (row + SETTING_ROWS - 1) % SETTING_ROWS;
+                    continue;
+                }
+                Some(Ok(Key::Down)) => {
+                    row = (row + 1) % SETTING_ROWS;
+                    continue;
+                }
+                Some(Ok(Key::Left)) => -1,
+                Some(Ok(Key::Right)) => 1,
+                // the name is typed in rather than picked
+                Some(Ok(Key::Char('\n'))) if row == SETTING_ROWS - 1 => 0,
+                Some(Ok(Key::Esc | Key::Char('\n'))) | None => break,
+                Some(Ok(_)) => continue,
+                Some(Err(error)) => return Err(error),
+            };
+            let settings = &mut self.settings;
+            match row {
+                0 => settings.tileset = cycle(&Tileset::ALL, settings.tileset, step),
+                1 => settings.keybindings = cycle(&Preset::ALL, settings.keybindings, step),
+                2 => settings.difficulty = cycle(&Difficulty::ALL, settings.difficulty, step),
+                3 => settings.speedrun_timer = !settings.speedrun_timer,
+                _ => self.ask_name(stdout, keys)?,
+            }
+        }
+
+        // keep the settings for the next time the game starts
+        if let Err(error) = self.settings.save(Path::new(SETTINGS_FILE)) {
+            self.status = self
+                .catalog
+                .message("settings-save-failed", &[("error", error.to_string())]);
+        }
+        match KeyBindings::load(&self.files.keybindings, self.settings.keybindings) {
+            Ok(keybindings) => self.keybindings = keybindings,
+            Err(error) => {
+                self.status = self
+                    .catalog
+                    .message("settings-invalid-keybindings", &[("error", error)])
+            }
+        }
+        match Balance::load(&self.files.balance, self.settings.difficulty) {
+            Ok(balance) => self.balance = balance,
+            Err(error) => {
+                self.status = self
+                    .catalog
+                    .message("settings-invalid-balance", &[("error", error)])
+            }
+        }
+        Ok(())
+    }
+
+    // The menu entries, with the selected one highlighted
+    fn frame(&self) -> String {
+        let mut frame = format!(
+            "\x1b[1m\x1b[93m{}\x1b[0m\r\n{}\r\n\r\n",
+            self.catalog.message("menu-title", &[]),
+            self.catalog.message("menu-subtitle", &[])
+        );
+        let has_save = Path::new(SAVE_FILE).exists();
+        for (index, entry) in Entry::ALL.into_iter().enumerate() {
+            let label = self.catalog.message(entry.message_id(), &[]);
+            if entry == Entry::Continue && !has_save && index != self.selected {
+                frame.push_str(&format!("   \x1b[2m{}\x1b[0m\r\n", label)); // Nothing to continue
+            } else {
+                frame.push_str(&entry_line(&label, index == self.selected));
+            }
+        }
+        frame.push_str(&format!("\r\n{}\r\n\r\n", self.status));
+        frame
+    }
+
+    fn render<W: Write>(&self, stdout: &mut W) -> Result<(), io::Error> {
+        write!(
+            stdout,
+            "{}{}{}{}",
+            clear::All,
+            cursor::Goto(1, 1),
+            self.frame(),
+            self.catalog.message("menu-help", &[])
+        )?;
+        stdout.flush()
+    }
+
+    fn render_settings<W: Write>(&self, stdout: &mut W, row: usize) -> Result<(), io::Error> {
+        let catalog = self.catalog;
+        let message = |id| catalog.message(id, &[]);
+        let rows = [
+            (
+                "settings-tileset",
+                message(self.settings.tileset.message_id()),
+            ),
+            (
+                "settings-keybindings",
+                message(self.settings.keybindings.message_id()),
+            ),
+            (
+                "settings-difficulty",
+                message(self.settings.difficulty.message_id()),
+            ),
+            (
+                "settings-speedrun-timer",
+                message(if self.settings.speedrun_timer {
+                    "settings-on"
+                } else {
+                    "settings-off"
+                }),
+            ),
+            ("settings-name", self.settings.player_name.clone()),
+        ];
+        let mut frame = format!(
+            "\x1b[1m\x1b[93m{}\x1b[0m\r\n\r\n",
+            catalog.message("settings-title", &[])
+        );
+        for (index, (name, value)) in rows.into_iter().enumerate() {
+            let value = format!("< {} >", value);
+            let value = if index == row {
+                format!("\x1b[7m{}\x1b[0m", value)
+            } else {
+                value
+            };
+            frame.push_str(&format!(
+                "  {:<20} {}\r\n",
+                catalog.message(name, &[]),
+                value
+            ));
+        }
+        frame.push_str(&format!(
+            "\r\n{}\r\n",
+            catalog.message("settings-help", &[])
+        ));
+
+        write!(stdout, "{}{}{}", clear::All, cursor::Goto(1, 1), frame)?;
+        stdout.flush()
+    }
+}
+
+// A line of a list to choose from, the selected entry in reverse video
+fn entry_line(label: &str, selected: bool) -> String {
+    if selected {
+        format!("  \x1b[7m {} \x1b[0m\r\n", label)
+    } else {
+        format!("   {}\r\n", label)
+    }
+}
+
+// The option `step` places away from `current`, wrapping around
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, step: isize) -> T {
+    let index = options
+        .iter()
+        .position(|&option| option == current)
+        .unwrap_or(0);
+    options[(index as isize + step).rem_euclid(options.len() as isize) as usize]
+}
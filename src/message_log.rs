@@ -0,0 +1,321 @@
+use crate::i18n::Catalog;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+use termion::terminal_size;
+use termion::{clear, cursor, event::Key};
+
+// How many messages the log keeps before dropping the oldest
+pub const MESSAGE_LOG_CAPACITY: usize = 500;
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    #[default]
+    Info,
+    Combat,
+    Loot,
+    Warning,
+}
+
+impl Category {
+    // ANSI colour the message is drawn with
+    pub fn color(self) -> &'static str {
+        match self {
+            Category::Info => "\x1b[0m",
+            Category::Combat => "\x1b[91m",
+            Category::Loot => "\x1b[93m",
+            Category::Warning => "\x1b[95m",
+        }
+    }
+}
+
+pub struct LogEntry {
+    pub turn: u32,
+    // time since the game started
+    pub time: Duration,
+    pub category: Category,
+    pub text: String,
+    // how many times in a row the same message was logged
+    pub repeat: u32,
+}
+
+impl LogEntry {
+    pub fn format(&self) -> String {
+        let seconds = self.time.as_secs();
+        let repeat = if self.repeat > 1 {
+            format!(" (x{})", self.repeat)
+        } else {
+            String::new()
+        };
+        format!(
+            "\x1b[2m[{:>4}] {:02}:{:02}\x1b[0m {}{}{}\x1b[0m",
+            self.turn,
+            seconds / 60,
+            seconds % 60,
+            self.category.color(),
+            self.text,
+            repeat
+        )
+    }
+}
+
+pub struct MessageLog {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    started: Instant,
+}
+
+impl MessageLog {
+    pub fn new(capacity: usize) -> MessageLog {
+        MessageLog {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn push(&mut self, turn: u32, category: Category, text: String) {
+        // collapse repeated messages, walking around would flood the log otherwise
+        if let Some(last) = self.entries.back_mut() {
+            if last.text == text {
+                last.repeat += 1;
+                last.turn = turn;
+                last.time = self.started.elapsed();
+                return;
+            }
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            turn,
+            time: self.started.elapsed(),
+            category,
+            text,
+            repeat: 1,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // The `count` most recent entries, oldest first
+    pub fn last(&self, count: usize) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().skip(self.len().saturating_sub(count))
+    }
+
+    // Index of the first entry from `start` (going forward or backward) containing `query`
+    fn find(&self, query: &str, start: usize, forward: bool) -> Option<usize> {
+        let query = query.to_lowercase();
+        let matches = |index: &usize| self.entries[*index].text.to_lowercase().contains(&query);
+        if forward {
+            (start..self.len()).find(matches)
+        } else {
+            (0..=start.min(self.len().saturating_sub(1)))
+                .rev()
+                .find(matches)
+        }
+    }
+}
+
+// Full screen scrollback of the message log. Arrows and Page Up/Down scroll,
+// `/` searches, `n`/`N` jump to the next/previous match, Escape closes it.
+pub fn show_history<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+    stdout: &mut W,
+    keys: &mut I,
+    log: &MessageLog,
+    catalog: &Catalog,
+) -> Result<(), io::Error> {
+    let (_, term_height) = terminal_size()?;
+    // lines left once the title, help and search lines are drawn
+    let page_height = (term_height as usize).saturating_sub(4).max(1);
+    scroll_history(stdout, keys, log, catalog, page_height)
+}
+
+fn scroll_history<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+    stdout: &mut W,
+    keys: &mut I,
+    log: &MessageLog,
+    catalog: &Catalog,
+    page_height: usize,
+) -> Result<(), io::Error> {
+    let max_top = log.len().saturating_sub(page_height);
+
+    let mut top = max_top;
+    let mut query = String::new();
+    let mut selected: Option<usize> = None;
+    let mut status = String::new();
+
+    loop {
+        let mut frame = format!(
+            "\x1b[1m\x1b[93m{}\x1b[0m\r\n{}\r\n",
+            catalog.message("log-title", &[]),
+            catalog.message("log-help", &[])
+        );
+        for (index, entry) in log.entries.iter().enumerate().skip(top).take(page_height) {
+            if Some(index) == selected {
+                frame.push_str(&format!("\x1b[7m>\x1b[0m {}\r\n", entry.format()));
+            } else {
+                frame.push_str(&format!("  {}\r\n", entry.format()));
+            }
+        }
+        frame.push_str(&status);
+        write!(stdout, "{}{}{}", clear::All, cursor::Goto(1, 1), frame)?;
+        stdout.flush()?;
+
+        let Some(key_event) = keys.next() else {
+            return Ok(());
+        };
+        status.clear();
+        let key = key_event?;
+        // only searching moves to the match, scrolling leaves it behind
+        let searched = matches!(key, Key::Char('/' | 'n' | 'N'));
+        match key {
+            Key::Up => top = top.saturating_sub(1),
+            Key::Down => top = (top + 1).min(max_top),
+            Key::PageUp => top = top.saturating_sub(page_height),
+            Key::PageDown => top = (top + page_height).min(max_top),
+            Key::Home => top = 0,
+            Key::End => top = max_top,
+            Key::Char('/') => {
+                query = read_query(
+                    stdout,
+                    keys,
+                    &frame,
+                    &catalog.message("log-search-prompt", &[]),
+                )?;
+                selected = log.find(&query, top, true);
+            }
+            Key::Char('n') if !query.is_empty() => {
+                selected = log.find(&query, selected.map_or(top, |index| index + 1), true)
+            }
+            Key::Char('N') if !query.is_empty() => {
+                selected = selected
+                    .and_then(|index| index.checked_sub(1))
+                    .and_then(|start| log.find(&query, start, false))
+            }
+            Key::Esc | Key::Char('q') => return Ok(()),
+            _ => {}
+        }
+        if !searched {
+            continue;
+        }
+        if let Some(index) = selected {
+            // keep the match on screen
+            if index < top || index >= top + page_height {
+                top = index.saturating_sub(page_height / 2).min(max_top);
+            }
+        } else if !query.is_empty() {
+            status = catalog.message("log-no-match", &[("query", query.clone())]);
+        }
+    }
+}
+
+// Read a line of text typed under the frame, Escape gives an empty line
+pub fn read_query<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+    stdout: &mut W,
+    keys: &mut I,
+    frame: &str,
+    prompt: &str,
+) -> Result<String, io::Error> {
+    let mut query = String::new();
+    loop {
+        write!(
+            stdout,
+            "{}{}{}{} {}",
+            clear::All,
+            cursor::Goto(1, 1),
+            frame,
+            prompt,
+            query
+        )?;
+        stdout.flush()?;
+        match keys.next() {
+            Some(Ok(Key::Char('\n'))) | None => return Ok(query),
+            Some(Ok(Key::Esc)) => return Ok(String::new()),
+            Some(Ok(Key::Backspace)) => {
+                query.pop();
+            }
+            Some(Ok(Key::Char(c))) => query.push(c),
+            Some(Ok(_)) => {}
+            Some(Err(error)) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Language;
+
+    // The frames the scrollback draws for the keys, on a page of 10 lines
+    fn frames(log: &MessageLog, keys: &[Key]) -> Vec<String> {
+        let mut output = Vec::new();
+        let mut keys = keys.iter().map(|&key| Ok(key));
+        let catalog = Catalog::new(Language::English);
+        scroll_history(&mut output, &mut keys, log, &catalog, 10).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .split(&clear::All.to_string())
+            .skip(1)
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn log() -> MessageLog {
+        let mut log = MessageLog::new(MESSAGE_LOG_CAPACITY);
+        for turn in 0..50 {
+            log.push(turn, Category::Info, format!("message {}", turn));
+        }
+        log
+    }
+
+    #[test]
+    fn repeated_messages_are_collapsed() {
+        let mut log = MessageLog::new(3);
+        for text in ["a", "b", "b", "c", "d"] {
+            log.push(0, Category::Info, text.to_string());
+        }
+        let texts: Vec<(&str, u32)> = log
+            .last(5)
+            .map(|entry| (entry.text.as_str(), entry.repeat))
+            .collect();
+        assert_eq!(texts, [("b", 2), ("c", 1), ("d", 1)]);
+    }
+
+    #[test]
+    fn searches_select_the_match() {
+        let mut keys = vec![Key::Home, Key::Char('/')];
+        keys.extend("message 30\n".chars().map(Key::Char));
+        let frames = frames(&log(), &keys);
+        let last = frames.last().unwrap();
+        assert!(last.contains("\x1b[7m>\x1b[0m \x1b[2m[  30]"));
+        assert!(!last.contains("message 5\x1b"));
+
+        // nothing matches before it
+        keys.push(Key::Char('N'));
+        let frames = self::frames(&log(), &keys);
+        assert!(frames
+            .last()
+            .unwrap()
+            .contains("No message matches \"message 30\""));
+    }
+
+    #[test]
+    fn history_scrolls_after_a_search() {
+        let mut keys = vec![Key::Home, Key::Char('/')];
+        keys.extend("message 30\n".chars().map(Key::Char));
+        keys.extend([Key::PageUp, Key::PageUp]);
+        let frames = frames(&log(), &keys);
+        let last = frames.last().unwrap();
+        assert!(last.contains("message 5\x1b"));
+        assert!(!last.contains("message 30"));
+    }
+}
@@ -0,0 +1,419 @@
+use crate::i18n::Language;
+use crate::world::registry::{self, ItemDef, NpcDef, Registry, TileDef};
+use crate::world::rules::{Rules, RULES_FILE};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Default directory the content packs are loaded from
+pub const MODS_DIR: &str = "mods";
+// Each pack is a directory of mods/, loaded in name order, which may hold:
+//
+//   types.toml          new tile, NPC and item types
+//   rules.toml          interaction rules, like the game's rules.toml
+//   scripts/*.rhai      scripts, see scripting.rs
+//   locales/<code>.ftl  names and messages, e.g. `tile-lava = Lava`
+//
+// New types look like something of their own but behave like the built-in
+// type they are `like`. For example, mods/volcano/types.toml:
+//
+//   [[tile]]
+//   id = "lava"
+//   like = "spider-web"           # deadly to walk on
+//   glyph = "🌋"                  # two columns wide, like the other emoji
+//   ascii = "\u001b[91m&\u001b[39m"
+//   map_char = "&"                # for map files and saves
+//
+//   [[npc]]
+//   id = "wolf"
+//   like = "troll"
+//   glyph = "🐺"
+//   ascii = "\u001b[90mw\u001b[39m"
+//   speed = 120                   # default: the speed of the troll
+//   lives_on = "grass"            # default: the tile of the troll
+//   count = 3                     # how many a generated world gets
+//
+//   [[item]]
+//   id = "golden-sword"
+//   like = "sword"                # kills trolls like a sword does
+//   glyph = "⚔️ "
+//   ascii = "\u001b[93m/\u001b[39m"
+//
+// A type with the id of one defined before, built-in or by an earlier pack,
+// replaces it and the conflict is reported.
+const TYPES_FILE: &str = "types.toml";
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TileEntry {
+    id: String,
+    like: String,
+    glyph: String,
+    ascii: String,
+    map_char: char,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NpcEntry {
+    id: String,
+    like: String,
+    glyph: String,
+    ascii: String,
+    speed: Option<u32>,
+    lives_on: Option<String>,
+    #[serde(default)]
+    count: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ItemEntry {
+    id: String,
+    like: String,
+    glyph: String,
+    ascii: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TypesFile {
+    #[serde(default)]
+    tile: Vec<TileEntry>,
+    #[serde(default)]
+    npc: Vec<NpcEntry>,
+    #[serde(default)]
+    item: Vec<ItemEntry>,
+}
+
+// Everything the packs bring besides their types, which `load` installs
+pub struct Mods {
+    // pack names, in load order
+    pub packs: Vec<String>,
+    // the rules of every pack, in load order
+    pub rules: Rules,
+    pub locales: Vec<(Language, String)>,
+    pub scripts: Vec<PathBuf>,
+    // "<pack>: <type> '<id>' replaces the one of <other>" for every id
+    // defined twice
+    pub conflicts: Vec<String>,
+}
+
+impl Mods {
+    // Load the packs of the directory and install their types, or only the
+    // built-in types when there is no such directory. Loading them again may
+    // change and add types, not remove any.
+    pub fn load(dir: &Path) -> Result<Mods, String> {
+        let mut mods = Mods {
+            packs: Vec::new(),
+            rules: Rules::empty(),
+            locales: Vec::new(),
+            scripts: Vec::new(),
+            conflicts: Vec::new(),
+        };
+        let mut paths = Vec::new();
+        if dir.exists() {
+            let entries =
+                fs::read_dir(dir).map_err(|error| format!("{}: {}", dir.display(), error))?;
+            paths = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_dir())
+                .collect();
+            paths.sort();
+        }
+
+        // types first: the rules of a pack may use the types of any pack
+        let mut registry = Registry::built_in();
+        let mut origins = Origins::default();
+        for path in &paths {
+            let pack = pack_name(path);
+            let types = path.join(TYPES_FILE);
+            if types.exists() {
+                add_types(
+                    &mut registry,
+                    &mut origins,
+                    &pack,
+                    &types,
+                    &mut mods.conflicts,
+                )
+                .map_err(|message| format!("{}: {}", types.display(), message))?;
+            }
+            mods.packs.push(pack);
+        }
+        let previous = registry::registry();
+        registry.keeps(previous)?;
+        registry::install(registry);
+
+        for path in &paths {
+            if let Err(message) = mods.load_pack(path) {
+                registry::restore(previous);
+                return Err(message);
+            }
+        }
+        Ok(mods)
+    }
+
+    // Everything of the pack but its types
+    fn load_pack(&mut self, path: &Path) -> Result<(), String> {
+        let rules = path.join(RULES_FILE);
+        if rules.exists() {
+            let content = fs::read_to_string(&rules)
+                .map_err(|error| format!("{}: {}", rules.display(), error))?;
+            let pack_rules = Rules::parse(&content)
+                .map_err(|message| format!("{}: {}", rules.display(), message))?;
+            self.rules.append(pack_rules);
+        }
+        for language in Language::ALL {
+            let locale = path
+                .join("locales")
+                .join(format!("{}.ftl", language.code()));
+            if locale.exists() {
+                let source = fs::read_to_string(&locale)
+                    .map_err(|error| format!("{}: {}", locale.display(), error))?;
+                self.locales.push((language, source));
+            }
+        }
+        let scripts = path.join("scripts");
+        if scripts.is_dir() {
+            self.scripts.push(scripts);
+        }
+        Ok(())
+    }
+}
+
+// Which pack defined each type id, to report the conflicts
+#[derive(Default)]
+struct Origins {
+    tiles: HashMap<String, String>,
+    npcs: HashMap<String, String>,
+    items: HashMap<String, String>,
+}
+
+fn pack_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+}
+
+fn add_types(
+    registry: &mut Registry,
+    origins: &mut Origins,
+    pack: &str,
+    path: &Path,
+    conflicts: &mut Vec<String>,
+) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let types: TypesFile = toml::from_str(&content).map_err(|error| error.to_string())?;
+    let mut conflict = |origins: &mut HashMap<String, String>, kind: &str, id: &str| {
+        let other = origins.insert(id.to_string(), pack.to_string());
+        conflicts.push(format!(
+            "{}: {} '{}' replaces the one of {}",
+            pack,
+            kind,
+            id,
+            other.unwrap_or_else(|| "the game".to_string())
+        ));
+    };
+
+    for entry in types.tile {
+        let like = registry
+            .find_tile(&entry.like)
+            .ok_or_else(|| format!("tile '{}': unknown tile '{}'", entry.id, entry.like))?;
+        let def = TileDef {
+            like: registry.tile(like).like,
+            glyph: entry.glyph,
+            ascii: entry.ascii,
+            map_char: entry.map_char,
+            id: entry.id,
+        };
+        let id = def.id.clone();
+        if registry.add_tile(def)? {
+            conflict(&mut origins.tiles, "tile", &id);
+        } else {
+            origins.tiles.insert(id, pack.to_string());
+        }
+    }
+
+    for entry in types.npc {
+        let like = registry
+            .find_npc(&entry.like)
+            .ok_or_else(|| format!("npc '{}': unknown npc '{}'", entry.id, entry.like))?;
+        let lives_on = match &entry.lives_on {
+            Some(id) => registry
+                .find_tile(id)
+                .ok_or_else(|| format!("npc '{}': unknown tile '{}'", entry.id, id))?,
+            None => registry.npc(like).lives_on,
+        };
+        let def = NpcDef {
+            like: registry.npc(like).like,
+            glyph: entry.glyph,
+            ascii: entry.ascii,
+            speed: entry.speed.unwrap_or(registry.npc(like).speed),
+            lives_on,
+            count: entry.count,
+            id: entry.id,
+        };
+        let id = def.id.clone();
+        if registry.add_npc(def) {
+            conflict(&mut origins.npcs, "npc", &id);
+        } else {
+            origins.npcs.insert(id, pack.to_string());
+        }
+    }
+
+    for entry in types.item {
+        let like = registry
+            .find_item(&entry.like)
+            .ok_or_else(|| format!("item '{}': unknown item '{}'", entry.id, entry.like))?;
+        let def = ItemDef {
+            like: registry.item(like).like,
+            glyph: entry.glyph,
+            ascii: entry.ascii,
+            id: entry.id,
+        };
+        let id = def.id.clone();
+        if registry.add_item(def) {
+            conflict(&mut origins.items, "item", &id);
+        } else {
+            origins.items.insert(id, pack.to_string());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::entities::{ItemType, NPCType};
+    use crate::world::terrain::{TileType, TERRAIN_WIDTH};
+    use crate::world::{Balance, World};
+
+    // A mods directory with the given files, in a fresh temporary directory
+    fn mods_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("game-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    const BOG: &str = r#"
+[[tile]]
+id = "bog"
+like = "spider-web"
+glyph = "🟫"
+ascii = "%"
+map_char = "%"
+
+[[npc]]
+id = "wolf"
+like = "troll"
+glyph = "🐺"
+ascii = "w"
+count = 2
+
+[[item]]
+id = "machete"
+like = "axe"
+glyph = "🔪"
+ascii = "!"
+"#;
+
+    #[test]
+    fn packs() {
+        // the types of the packs are gone once the test is over, even if it fails
+        let _restore = registry::Restore::current();
+        let dir = mods_dir(
+            "mods",
+            &[
+                ("a-swamp/types.toml", BOG),
+                (
+                    "a-swamp/rules.toml",
+                    "[[rule]]\ntile = \"bog\"\nrequires = [\"machete\"]\nbecomes = \"grass\"\nmessage = \"bog-cleared\"",
+                ),
+                ("a-swamp/locales/en.ftl", "tile-bog = Bog"),
+                (
+                    "b-hills/types.toml",
+                    "[[npc]]\nid = \"wolf\"\nlike = \"spider\"\nglyph = \"🐺\"\nascii = \"w\"",
+                ),
+            ],
+        );
+        let mods = Mods::load(&dir).unwrap();
+        assert_eq!(mods.packs, ["a-swamp", "b-hills"]);
+        assert_eq!(
+            mods.conflicts,
+            ["b-hills: npc 'wolf' replaces the one of a-swamp"]
+        );
+        assert_eq!(mods.locales.len(), 1);
+
+        let bog = TileType::from_id("bog").unwrap();
+        assert!(bog.like() == TileType::SPIDER_WEB && TileType::from_map_char('%') == Some(bog));
+        // the later pack won: a wolf with the speed and tile of a spider
+        let wolf = NPCType::from_id("wolf").unwrap();
+        assert!(
+            wolf.like() == NPCType::SPIDER
+                && wolf.speed() == 100
+                && registry::registry().npc(wolf).count == 0
+        );
+        let machete = ItemType::from_id("machete").unwrap();
+        assert!(machete.is(ItemType::AXE) && !ItemType::AXE.is(machete));
+
+        // the bog's own rule first, then the ones of the spider web
+        let rules = Rules::load(Path::new("no-such-rules.toml"), mods.rules).unwrap();
+        let mut world = World::with_seed(0, Balance::default());
+        (world.player.x, world.player.y) = (10, 10);
+        world.npcs.clear();
+        world.terrain.tiles[10 * TERRAIN_WIDTH + 11] = bog;
+        world.player.inventory = vec![machete];
+        assert!(rules.apply(&mut world, 11, 10).unwrap().message_id() == "bog-cleared");
+        assert!(world.terrain.tiles[10 * TERRAIN_WIDTH + 11] == TileType::GRASS);
+        world.terrain.tiles[10 * TERRAIN_WIDTH + 11] = bog;
+        world.player.inventory.clear();
+        assert!(rules.apply(&mut world, 11, 10).unwrap().message_id() == "trapped-in-web");
+        assert!(world.player.dead);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn installed_types_are_restored_and_kept() {
+        let dir = mods_dir("restored-mods", &[("a-swamp/types.toml", BOG)]);
+        {
+            let _restore = registry::Restore::current();
+            Mods::load(&dir).unwrap();
+            let installed = registry::registry();
+            // the same packs again do not make another registry
+            Mods::load(&dir).unwrap();
+            assert!(std::ptr::eq(installed, registry::registry()));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(TileType::from_id("bog").is_none());
+        assert!(Mods::load(Path::new("no-such-mods")).is_ok());
+    }
+
+    #[test]
+    fn invalid_packs() {
+        for (types, error) in [
+            (
+                "[[tile]]\nid = \"moss\"\nlike = \"grass\"\nglyph = \"x\"\nascii = \"x\"\nmap_char = \"T\"",
+                "tile 'moss': map character 'T' is already used by 'tree'",
+            ),
+            (
+                "[[item]]\nid = \"net\"\nlike = \"rope\"\nglyph = \"x\"\nascii = \"x\"",
+                "item 'net': unknown item 'rope'",
+            ),
+            (
+                "[[npc]]\nid = \"crab\"\nlike = \"fish\"\nglyph = \"x\"\nascii = \"x\"\nlives_on = \"beach\"",
+                "npc 'crab': unknown tile 'beach'",
+            ),
+        ] {
+            let dir = mods_dir("invalid-mods", &[("pack/types.toml", types)]);
+            let message = Mods::load(&dir).err().unwrap();
+            assert!(message.contains(error), "{}: {}", error, message);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::i18n::Catalog;
+use crate::message_log::Category;
+use crate::world::entities::ItemType;
+use crate::world::stats::Stat;
+
+// What happened when the player tried to do something. Turned into text
+// through the message catalog, so the game core never deals with wording.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Welcome,
+    OutOfBounds,
+    TrollBlocksPath,
+    TrollDefeated { loot: Option<ItemType> },
+    SpiderDefeated { loot: Option<ItemType> },
+    FishCaught { loot: ItemType },
+    FishIgnored,
+    FishOutOfReach,
+    // a rule of the interaction table applied, see world::rules
+    Interaction(Interaction),
+    Unreachable,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Interaction {
+    // id of the message in the catalog
+    pub message: String,
+    pub category: Category,
+    // the item the message talks about, if any
+    pub item: Option<ItemType>,
+    // time the action took
+    pub cost: u32,
+    // the stat of the game the interaction counts for
+    pub counts: Option<Stat>,
+}
+
+impl Outcome {
+    pub fn message_id(&self) -> &str {
+        match self {
+            Outcome::Welcome => "welcome",
+            Outcome::OutOfBounds => "out-of-bounds",
+            Outcome::TrollBlocksPath => "troll-blocks-path",
+            Outcome::TrollDefeated { loot: None } => "troll-defeated",
+            Outcome::TrollDefeated { loot: Some(_) } => "troll-defeated-loot",
+            Outcome::SpiderDefeated { loot: None } => "spider-defeated",
+            Outcome::SpiderDefeated { loot: Some(_) } => "spider-defeated-loot",
+            Outcome::FishCaught { .. } => "fish-caught",
+            Outcome::FishIgnored => "fish-ignored",
+            Outcome::FishOutOfReach => "fish-out-of-reach",
+            Outcome::Interaction(interaction) => &interaction.message,
+            Outcome::Unreachable => "unreachable",
+        }
+    }
+
+    pub fn category(&self) -> Category {
+        match self {
+            Outcome::TrollDefeated { .. }
+            | Outcome::SpiderDefeated { .. }
+            | Outcome::FishCaught { .. } => Category::Combat,
+            Outcome::OutOfBounds
+            | Outcome::TrollBlocksPath
+            | Outcome::FishOutOfReach
+            | Outcome::Unreachable => Category::Warning,
+            Outcome::Welcome | Outcome::FishIgnored => Category::Info,
+            Outcome::Interaction(interaction) => interaction.category,
+        }
+    }
+
+    // The item the message talks about, if any
+    fn item(&self) -> Option<ItemType> {
+        match *self {
+            Outcome::TrollDefeated { loot } | Outcome::SpiderDefeated { loot } => loot,
+            Outcome::FishCaught { loot } => Some(loot),
+            Outcome::Interaction(ref interaction) => interaction.item,
+            _ => None,
+        }
+    }
+
+    pub fn message(&self, catalog: &Catalog) -> String {
+        let mut args = Vec::new();
+        if let Some(item) = self.item() {
+            args.push(("item", item_name(item, catalog)));
+        }
+        catalog.message(self.message_id(), &args)
+    }
+}
+
+pub fn item_name(item: ItemType, catalog: &Catalog) -> String {
+    catalog.message(&format!("item-{}", item.id()), &[])
+}
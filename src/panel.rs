@@ -0,0 +1,156 @@
+use crate::i18n::Catalog;
+use crate::keybindings::{key_name, Action, KeyBindings};
+use crate::outcome::item_name;
+use crate::tileset::Tileset;
+use crate::world::entities::{ItemType, NPCType};
+use crate::world::terrain::{TileType, TERRAIN_HEIGHT, TERRAIN_WIDTH};
+use crate::world::{DayPhase, World};
+
+// Actions listed on the controls line, movement aside
+const PANEL_ACTIONS: [Action; 6] = [
+    Action::Wait,
+    Action::MessageLog,
+    Action::Help,
+    Action::TogglePanel,
+    Action::Save,
+    Action::Quit,
+];
+
+// The help panel drawn above the map: what the visible glyphs are, the main
+// controls, and a hint about what the player is standing next to.
+pub fn draw(
+    world: &World,
+    tileset: Tileset,
+    keybindings: &KeyBindings,
+    catalog: &Catalog,
+) -> String {
+    let heading = |id: &str| format!("\x1b[1m\x1b[93m{}\x1b[0m", catalog.message(id, &[]));
+
+    let mut panel = format!(
+        "{} {}\r\n",
+        heading("panel-legend"),
+        legend(world, tileset, catalog)
+    );
+    panel.push_str(&format!(
+        "{} {}\r\n",
+        heading("panel-controls"),
+        controls(keybindings, catalog)
+    ));
+    panel.push_str(&format!(
+        "{} {}\r\n",
+        heading("panel-hint"),
+        catalog.message(hint(world), &[])
+    ));
+    panel
+}
+
+// Every kind of tile, NPC and item the player can see right now
+fn legend(world: &World, tileset: Tileset, catalog: &Catalog) -> String {
+    let mut tiles = Vec::new();
+    for y in 0..TERRAIN_HEIGHT {
+        for x in 0..TERRAIN_WIDTH {
+            let tile_type = world.terrain.tiles[y * TERRAIN_WIDTH + x];
+            if !tiles.contains(&tile_type) && world.is_visible(x as i32, y as i32) {
+                tiles.push(tile_type);
+            }
+        }
+    }
+
+    let mut entries = Vec::new();
+    for tile_type in TileType::all().filter(|tile_type| tiles.contains(tile_type)) {
+        entries.push(format!(
+            "{} {}",
+            tileset.tile(tile_type),
+            catalog.message(&format!("tile-{}", tile_type.id()), &[])
+        ));
+    }
+    for npc_type in NPCType::all() {
+        if world
+            .npcs
+            .iter()
+            .any(|npc| npc.npc_type == npc_type && world.is_visible(npc.x, npc.y))
+        {
+            entries.push(format!(
+                "{} {}",
+                tileset.npc(npc_type),
+                catalog.message(&format!("npc-{}", npc_type.id()), &[])
+            ));
+        }
+    }
+    for &item in &world.player.inventory {
+        entries.push(format!(
+            "{} {}",
+            tileset.item(item),
+            item_name(item, catalog)
+        ));
+    }
+    entries.join("  ")
+}
+
+fn controls(keybindings: &KeyBindings, catalog: &Catalog) -> String {
+    let first_key = |action| keybindings.keys_for(action).next().map(key_name);
+
+    let movement: Vec<String> = [
+        Action::MoveNorth,
+        Action::MoveSouth,
+        Action::MoveWest,
+        Action::MoveEast,
+    ]
+    .into_iter()
+    .filter_map(first_key)
+    .collect();
+    let mut entries = vec![format!(
+        "{} {}",
+        catalog.message("panel-move", &[]),
+        movement.join("/")
+    )];
+    for action in PANEL_ACTIONS {
+        if let Some(key) = first_key(action) {
+            entries.push(format!(
+                "{} {}",
+                catalog.message(&action.message_id(), &[]),
+                key
+            ));
+        }
+    }
+    entries.join("   ")
+}
+
+// Message id of the most useful hint for where the player stands
+fn hint(world: &World) -> &'static str {
+    let has = |item| world.player.has(item);
+    let mut tiles = Vec::new();
+    let mut npcs = Vec::new();
+    for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+        let (x, y) = (world.player.x + dx, world.player.y + dy);
+        if x < 0 || y < 0 || x >= TERRAIN_WIDTH as i32 || y >= TERRAIN_HEIGHT as i32 {
+            continue;
+        }
+        tiles.push(world.terrain.tiles[y as usize * TERRAIN_WIDTH + x as usize].like());
+        npcs.extend(
+            world
+                .npcs
+                .iter()
+                .filter(|npc| npc.x == x && npc.y == y)
+                .map(|npc| npc.npc_type.like()),
+        );
+    }
+
+    if tiles.contains(&TileType::SPIDER_WEB) {
+        "hint-spider-web"
+    } else if npcs.contains(&NPCType::TROLL) {
+        "hint-troll"
+    } else if npcs.contains(&NPCType::FISH) && !has(ItemType::HARPOON) {
+        "hint-fish"
+    } else if tiles.contains(&TileType::CASTLE) && !has(ItemType::KEY) {
+        "hint-castle"
+    } else if tiles.contains(&TileType::WATER) && !has(ItemType::SNORKEL) {
+        "hint-water"
+    } else if tiles.contains(&TileType::TREE) && !has(ItemType::AXE) {
+        "hint-tree"
+    } else if world.clock().phase() == DayPhase::Night {
+        "hint-night"
+    } else {
+        "hint-explore"
+    }
+}
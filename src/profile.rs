@@ -0,0 +1,303 @@
+use crate::i18n::Catalog;
+use crate::world::{RunStats, World};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use termion::{clear, cursor, event::Key};
+
+// Default location of the player profile
+pub const PROFILE_FILE: &str = "profile.json";
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Achievement {
+    TrollSlayer,
+    Pacifist,
+    Lumberjack,
+    Swimmer,
+    Speedrunner,
+    CaughtInTheWeb,
+    Veteran,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 7] = [
+        Achievement::TrollSlayer,
+        Achievement::Pacifist,
+        Achievement::Lumberjack,
+        Achievement::Swimmer,
+        Achievement::Speedrunner,
+        Achievement::CaughtInTheWeb,
+        Achievement::Veteran,
+    ];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Achievement::TrollSlayer => "troll-slayer",
+            Achievement::Pacifist => "pacifist",
+            Achievement::Lumberjack => "lumberjack",
+            Achievement::Swimmer => "swimmer",
+            Achievement::Speedrunner => "speedrunner",
+            Achievement::CaughtInTheWeb => "caught-in-the-web",
+            Achievement::Veteran => "veteran",
+        }
+    }
+
+    pub fn name(self, catalog: &Catalog) -> String {
+        catalog.message(&format!("achievement-{}", self.id()), &[])
+    }
+
+    pub fn description(self, catalog: &Catalog) -> String {
+        catalog.message(&format!("achievement-{}-description", self.id()), &[])
+    }
+
+    // Whether the game so far earns the achievement
+    fn achieved(self, world: &World, lifetime: &LifetimeStats) -> bool {
+        let run = &world.stats;
+        match self {
+            Achievement::TrollSlayer => run.trolls_slain >= 5,
+            // the axe only comes from a troll, one of them has to go
+            Achievement::Pacifist => run.won_at.is_some() && run.trolls_slain <= 1,
+            // the player's own chops, the trees storms knock down do not count
+            Achievement::Lumberjack => run.trees_chopped >= 100,
+            Achievement::Swimmer => run.tiles_swum >= 100,
+            Achievement::Speedrunner => run.won_at.is_some_and(|turn| turn <= 500),
+            Achievement::CaughtInTheWeb => run.deaths_by_web > 0,
+            Achievement::Veteran => lifetime.wins + u32::from(run.won_at.is_some()) >= 10,
+        }
+    }
+}
+
+// Totals over every finished game
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LifetimeStats {
+    pub games: u32,
+    pub wins: u32,
+    pub deaths: u32,
+    pub steps: u32,
+    pub tiles_swum: u32,
+    pub trees_chopped: u32,
+    pub trolls_slain: u32,
+    pub deaths_by_web: u32,
+    // fewest turns a game was won in
+    pub fastest_win: Option<u32>,
+}
+
+impl LifetimeStats {
+    // Add a game that ended, won or lost
+    pub fn add(&mut self, run: &RunStats) {
+        self.games += 1;
+        match run.won_at {
+            Some(turn) => {
+                self.wins += 1;
+                self.fastest_win = Some(self.fastest_win.map_or(turn, |fastest| fastest.min(turn)));
+            }
+            None => self.deaths += 1,
+        }
+        self.steps += run.steps;
+        self.tiles_swum += run.tiles_swum;
+        self.trees_chopped += run.trees_chopped;
+        self.trolls_slain += run.trolls_slain;
+        self.deaths_by_web += run.deaths_by_web;
+    }
+}
+
+// Lifetime stats and achievements, kept between games
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub lifetime: LifetimeStats,
+    pub achievements: Vec<Achievement>,
+}
+
+impl Profile {
+    // Load the profile, or a fresh one when there is no such file.
+    pub fn load(path: &Path) -> Result<Profile, io::Error> {
+        if !path.exists() {
+            return Ok(Profile::default());
+        }
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), error),
+            )
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)
+    }
+
+    // Unlock the achievements the game earned since the last call, and return them
+    pub fn unlock(&mut self, world: &World) -> Vec<Achievement> {
+        let unlocked: Vec<Achievement> = Achievement::ALL
+            .into_iter()
+            .filter(|achievement| {
+                !self.achievements.contains(achievement)
+                    && achievement.achieved(world, &self.lifetime)
+            })
+            .collect();
+        self.achievements.extend(&unlocked);
+        unlocked
+    }
+}
+
+// Full screen view of the stats of this game and of all games, and of the
+// achievements, closed by any key.
+pub fn show<W: Write, I: Iterator<Item = Result<Key, io::Error>>>(
+    stdout: &mut W,
+    keys: &mut I,
+    run: &RunStats,
+    profile: &Profile,
+    catalog: &Catalog,
+) -> Result<(), io::Error> {
+    let lifetime = &profile.lifetime;
+    let number = |value: Option<u32>| value.map_or("-".to_string(), |value| value.to_string());
+    let rows = [
+        ("stats-steps", Some(run.steps), Some(lifetime.steps)),
+        (
+            "stats-tiles-swum",
+            Some(run.tiles_swum),
+            Some(lifetime.tiles_swum),
+        ),
+        (
+            "stats-trees-chopped",
+            Some(run.trees_chopped),
+            Some(lifetime.trees_chopped),
+        ),
+        (
+            "stats-trolls-slain",
+            Some(run.trolls_slain),
+            Some(lifetime.trolls_slain),
+        ),
+        (
+            "stats-deaths-by-web",
+            Some(run.deaths_by_web),
+            Some(lifetime.deaths_by_web),
+        ),
+        ("stats-games", None, Some(lifetime.games)),
+        ("stats-wins", None, Some(lifetime.wins)),
+        ("stats-deaths", None, Some(lifetime.deaths)),
+        ("stats-time-to-win", run.won_at, lifetime.fastest_win),
+    ];
+
+    let mut frame = format!(
+        "\x1b[1m\x1b[93m{}\x1b[0m\r\n\r\n",
+        catalog.message("stats-title", &[])
+    );
+    frame.push_str(&format!(
+        "  {:<28} {:>10} {:>10}\r\n",
+        "",
+        catalog.message("stats-this-game", &[]),
+        catalog.message("stats-lifetime", &[])
+    ));
+    for (id, this_game, all_games) in rows {
+        frame.push_str(&format!(
+            "  {:<28} {:>10} {:>10}\r\n",
+            catalog.message(id, &[]),
+            number(this_game),
+            number(all_games)
+        ));
+    }
+
+    frame.push_str(&format!(
+        "\r\n\x1b[1m\x1b[93m{}\x1b[0m {}/{}\r\n\r\n",
+        catalog.message("stats-achievements", &[]),
+        profile.achievements.len(),
+        Achievement::ALL.len()
+    ));
+    for achievement in Achievement::ALL {
+        let line = format!(
+            "{:<20} {}",
+            achievement.name(catalog),
+            achievement.description(catalog)
+        );
+        if profile.achievements.contains(&achievement) {
+            frame.push_str(&format!("  [x] {}\r\n", line));
+        } else {
+            frame.push_str(&format!("  [ ] \x1b[2m{}\x1b[0m\r\n", line));
+        }
+    }
+    frame.push_str(&format!("\r\n{}\r\n", catalog.message("help-close", &[])));
+
+    write!(stdout, "{}{}{}", clear::All, cursor::Goto(1, 1), frame)?;
+    stdout.flush()?;
+
+    if let Some(key_event) = keys.next() {
+        key_event?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::terrain::TileType;
+    use crate::world::Balance;
+
+    #[test]
+    fn lumberjacks_chop_the_trees_themselves() {
+        let mut profile = Profile::default();
+        let mut world = World::with_seed(2, Balance::default());
+        // a storm knocked the whole forest down
+        for tile in world.terrain.tiles.iter_mut() {
+            if *tile == TileType::TREE {
+                *tile = TileType::GRASS;
+            }
+        }
+        world.stats.trees_chopped = 1;
+        assert!(!profile.unlock(&world).contains(&Achievement::Lumberjack));
+        world.stats.trees_chopped = 100;
+        assert!(profile.unlock(&world) == [Achievement::Lumberjack]);
+    }
+
+    #[test]
+    fn achievements_stay_unlocked_across_saves() {
+        let path = std::env::temp_dir().join(format!("game-profile-{}.json", std::process::id()));
+        let mut profile = Profile::default();
+        let mut world = World::with_seed(2, Balance::default());
+        world.stats.trolls_slain = 5;
+        world.stats.won_at = Some(300);
+        assert!(profile.unlock(&world) == [Achievement::TrollSlayer, Achievement::Speedrunner]);
+        profile.lifetime.add(&world.stats);
+        profile.save(&path).unwrap();
+
+        let mut loaded = Profile::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(loaded.achievements == [Achievement::TrollSlayer, Achievement::Speedrunner]);
+        assert_eq!(loaded.lifetime.wins, 1);
+        assert_eq!(loaded.lifetime.fastest_win, Some(300));
+        // unlocked once, not again in the next game
+        assert!(loaded.unlock(&world).is_empty());
+    }
+
+    #[test]
+    fn veterans_count_the_saved_wins() {
+        let path = std::env::temp_dir().join(format!("game-veteran-{}.json", std::process::id()));
+        let mut world = World::with_seed(2, Balance::default());
+        world.stats.won_at = Some(1000);
+        let mut profile = Profile::default();
+        for _ in 0..9 {
+            profile.lifetime.add(&world.stats);
+        }
+        profile.save(&path).unwrap();
+        let mut loaded = Profile::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(loaded.unlock(&world).contains(&Achievement::Veteran));
+    }
+
+    #[test]
+    fn missing_profiles_are_fresh_and_broken_ones_refused() {
+        let path = std::env::temp_dir().join(format!("game-broken-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(Profile::load(&path).unwrap().achievements.is_empty());
+        fs::write(&path, "{\"achievements\": [\"no-such-achievement\"]}").unwrap();
+        let error = Profile::load(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,380 @@
+use crate::events::GameEvent;
+use crate::game::{self, Content, Turn};
+use crate::keybindings::Action;
+use crate::world::terrain::{TileType, TERRAIN_HEIGHT, TERRAIN_WIDTH};
+use crate::world::weather::Weather;
+use crate::world::World;
+use schemars::{schema_for, JsonSchema};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+// The game over stdin and stdout for bots, with --stdio-protocol. Every line
+// is a JSON object, the bot sends commands and the game replies to each:
+//
+//   game: {"type":"hello","version":1,"game":"0.1.0"}
+//   bot:  {"command":"hello","version":1}
+//   game: {"type":"state","state":{...}}
+//   bot:  {"command":"move","direction":"north-east"}
+//   game: {"type":"turn","outcome":"tree-chopped","events":[...],"messages":[],"state":{...}}
+//
+// The game speaks first, then waits for the bot to say which version it
+// speaks, and stops there if it is not its own. The schema of the lines is
+// in docs/protocol.schema.json, written by --protocol-schema from the doc
+// comments of the types below.
+
+/// Bumped whenever the lines change in a way older bots would not understand
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// What the bot sends
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Command {
+    /// first, with the version of the protocol the bot speaks
+    Hello {
+        version: u32,
+    },
+    Move {
+        direction: Direction,
+    },
+    Wait,
+    /// the state, without playing a turn
+    State,
+}
+
+#[derive(Copy, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    North,
+    South,
+    West,
+    East,
+    NorthWest,
+    NorthEast,
+    SouthWest,
+    SouthEast,
+}
+
+impl Direction {
+    fn action(self) -> Action {
+        match self {
+            Direction::North => Action::MoveNorth,
+            Direction::South => Action::MoveSouth,
+            Direction::West => Action::MoveWest,
+            Direction::East => Action::MoveEast,
+            Direction::NorthWest => Action::MoveNorthWest,
+            Direction::NorthEast => Action::MoveNorthEast,
+            Direction::SouthWest => Action::MoveSouthWest,
+            Direction::SouthEast => Action::MoveSouthEast,
+        }
+    }
+}
+
+/// What the game sends
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Reply {
+    /// first, with the version of the protocol the game speaks
+    Hello {
+        version: u32,
+        game: String,
+    },
+    State {
+        state: State,
+    },
+    /// what a move or a wait did
+    Turn {
+        /// message id of what the move did, none when the player waited
+        outcome: Option<String>,
+        events: Vec<Event>,
+        /// what the scripts said this turn
+        messages: Vec<String>,
+        state: State,
+    },
+    /// the command was not understood or not allowed, nothing was played
+    Error {
+        message: String,
+    },
+}
+
+/// Everything about the world a bot may look at
+#[derive(Serialize, JsonSchema)]
+pub struct State {
+    pub turn: u32,
+    pub seed: u64,
+    pub width: usize,
+    pub height: usize,
+    /// one string per row, a map character per tile as in map files
+    pub map: Vec<String>,
+    /// the tile id of every map character in `map`
+    pub legend: BTreeMap<String, String>,
+    pub player: PlayerState,
+    pub npcs: Vec<NpcState>,
+    pub weather: Weather,
+    pub won: bool,
+    /// won or dead, the game takes no more moves
+    pub over: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PlayerState {
+    pub x: i32,
+    pub y: i32,
+    /// item ids
+    pub inventory: Vec<String>,
+    pub dead: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct NpcState {
+    /// NPC type id
+    pub id: String,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A GameEvent, with type ids instead of the types
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    PlayerMoved {
+        from: [i32; 2],
+        to: [i32; 2],
+    },
+    NpcKilled {
+        npc: String,
+        x: i32,
+        y: i32,
+    },
+    ItemAcquired {
+        item: String,
+    },
+    TileChanged {
+        x: i32,
+        y: i32,
+        from: String,
+        to: String,
+    },
+    PlayerDied,
+    GameWon,
+}
+
+impl From<&GameEvent> for Event {
+    fn from(event: &GameEvent) -> Event {
+        match *event {
+            GameEvent::PlayerMoved { from, to } => Event::PlayerMoved {
+                from: [from.0, from.1],
+                to: [to.0, to.1],
+            },
+            GameEvent::NpcKilled { npc_type, x, y } => Event::NpcKilled {
+                npc: npc_type.id().to_string(),
+                x,
+                y,
+            },
+            GameEvent::ItemAcquired(item) => Event::ItemAcquired {
+                item: item.id().to_string(),
+            },
+            GameEvent::TileChanged { x, y, from, to } => Event::TileChanged {
+                x,
+                y,
+                from: from.id().to_string(),
+                to: to.id().to_string(),
+            },
+            GameEvent::PlayerDied => Event::PlayerDied,
+            GameEvent::GameWon => Event::GameWon,
+        }
+    }
+}
+
+impl State {
+    pub fn of(world: &World) -> State {
+        let won = world.stats.won_at.is_some();
+        State {
+            turn: world.turn,
+            seed: world.seed,
+            width: TERRAIN_WIDTH,
+            height: TERRAIN_HEIGHT,
+            map: world
+                .terrain
+                .tiles
+                .chunks(TERRAIN_WIDTH)
+                .map(|row| row.iter().map(|tile| tile.map_char()).collect())
+                .collect(),
+            legend: TileType::all()
+                .map(|tile| (tile.map_char().to_string(), tile.id().to_string()))
+                .collect(),
+            player: PlayerState {
+                x: world.player.x,
+                y: world.player.y,
+                inventory: world
+                    .player
+                    .inventory
+                    .iter()
+                    .map(|item| item.id().to_string())
+                    .collect(),
+                dead: world.player.dead,
+            },
+            npcs: world
+                .npcs
+                .iter()
+                .map(|npc| NpcState {
+                    id: npc.npc_type.id().to_string(),
+                    x: npc.x,
+                    y: npc.y,
+                })
+                .collect(),
+            weather: world.weather,
+            won,
+            over: won || world.player.dead,
+        }
+    }
+}
+
+// The JSON schema of the commands and the replies
+pub fn schema() -> String {
+    let schema = serde_json::json!({
+        "version": PROTOCOL_VERSION,
+        "command": schema_for!(Command),
+        "reply": schema_for!(Reply),
+    });
+    serde_json::to_string_pretty(&schema).unwrap()
+}
+
+// Play the world with the commands read from `input` until it runs out
+pub fn run(
+    world: &mut World,
+    content: &Content,
+    input: impl BufRead,
+    output: &mut impl Write,
+) -> Result<(), io::Error> {
+    send(
+        output,
+        &Reply::Hello {
+            version: PROTOCOL_VERSION,
+            game: env!("CARGO_PKG_VERSION").to_string(),
+        },
+    )?;
+    let mut greeted = false;
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str(&line) {
+            Err(error) => error_reply(format!("invalid command: {}", error)),
+            Ok(Command::Hello { version }) if version != PROTOCOL_VERSION => {
+                send(
+                    output,
+                    &error_reply(format!(
+                        "unsupported version {}, this game speaks version {}",
+                        version, PROTOCOL_VERSION
+                    )),
+                )?;
+                return Ok(());
+            }
+            Ok(Command::Hello { .. }) => {
+                greeted = true;
+                Reply::State {
+                    state: State::of(world),
+                }
+            }
+            Ok(_) if !greeted => error_reply("say hello first".to_string()),
+            Ok(Command::State) => Reply::State {
+                state: State::of(world),
+            },
+            Ok(_) if State::of(world).over => error_reply("the game is over".to_string()),
+            Ok(Command::Move { direction }) => play(world, content, direction.action()),
+            Ok(Command::Wait) => play(world, content, Action::Wait),
+        };
+        send(output, &reply)?;
+    }
+    Ok(())
+}
+
+fn play(world: &mut World, content: &Content, action: Action) -> Reply {
+    let Turn {
+        outcome,
+        events,
+        output,
+    } = game::play_turn(world, content, action);
+    let mut messages = output.messages;
+    messages.extend(output.errors);
+    Reply::Turn {
+        outcome: outcome.map(|outcome| outcome.message_id().to_string()),
+        events: events.iter().map(Event::from).collect(),
+        messages,
+        state: State::of(world),
+    }
+}
+
+fn error_reply(message: String) -> Reply {
+    Reply::Error { message }
+}
+
+fn send(output: &mut impl Write, reply: &Reply) -> Result<(), io::Error> {
+    serde_json::to_writer(&mut *output, reply)?;
+    writeln!(output)?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Balance;
+    use serde_json::Value;
+
+    fn session(commands: &str) -> Vec<Value> {
+        let mut world = World::with_seed(5, Balance::default());
+        let mut output = Vec::new();
+        run(
+            &mut world,
+            &Content::default(),
+            commands.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn bots_play_after_the_handshake() {
+        let replies = session(
+            "{\"command\":\"wait\"}\n\
+             {\"command\":\"hello\",\"version\":1}\n\
+             {\"command\":\"move\",\"direction\":\"south-east\"}\n\
+             {\"command\":\"fly\"}\n\
+             {\"command\":\"state\"}\n",
+        );
+        let types: Vec<&str> = replies
+            .iter()
+            .map(|reply| reply["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, ["hello", "error", "state", "turn", "error", "state"]);
+        assert_eq!(replies[0]["version"], PROTOCOL_VERSION);
+        let state = &replies[2]["state"];
+        assert_eq!(state["map"].as_array().unwrap().len(), TERRAIN_HEIGHT);
+        assert_eq!(state["player"]["inventory"][0], "sword");
+        assert!(replies[3]["outcome"].is_string());
+        assert_eq!(replies[5]["state"]["turn"], 1);
+    }
+
+    #[test]
+    fn other_versions_are_turned_away() {
+        let replies = session("{\"command\":\"hello\",\"version\":99}\n{\"command\":\"state\"}\n");
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[1]["type"], "error");
+    }
+
+    #[test]
+    fn schema_docs_are_up_to_date() {
+        let docs = include_str!("../docs/protocol.schema.json");
+        assert!(
+            docs.trim_end() == schema(),
+            "run `game --protocol-schema > docs/protocol.schema.json`"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::outcome::Outcome;
+use crate::world::entities::NPC;
+use crate::world::terrain::{TileType, TERRAIN_WIDTH};
+use crate::world::World;
+
+// Time a normal action takes, which is also the energy an actor spends per turn
+pub const ACTION_COST: u32 = 100;
+
+// Speeds are a percentage of the normal speed
+pub const NORMAL_SPEED: u32 = 100;
+const SWIMMING_SPEED: u32 = 50;
+
+// Time the player's action took, depending on what happened
+pub fn action_cost(outcome: &Outcome) -> u32 {
+    match outcome {
+        // set by the rule, e.g. chopping a tree is hard work
+        Outcome::Interaction(interaction) => interaction.cost,
+        _ => ACTION_COST,
+    }
+}
+
+pub fn player_speed(world: &World) -> u32 {
+    let index = world.player.y as usize * TERRAIN_WIDTH + world.player.x as usize;
+    if world.terrain.tiles[index].like() == TileType::WATER {
+        SWIMMING_SPEED
+    } else {
+        NORMAL_SPEED
+    }
+}
+
+// How long an action of the given cost lasts for an actor of the given speed
+pub fn elapsed_time(cost: u32, speed: u32) -> u32 {
+    cost * NORMAL_SPEED / speed
+}
+
+// Give every NPC the energy it gained while `elapsed` time passed, and return the
+// indices of the NPCs in the order they get to act. Fast NPCs can appear several
+// times, slow ones only every few calls. NPCs act in rounds, in index order within
+// a round, so the result only depends on the NPCs and the elapsed time.
+pub fn schedule(npcs: &mut [NPC], elapsed: u32) -> Vec<usize> {
+    for npc in npcs.iter_mut() {
+        npc.energy += npc.npc_type.speed() * elapsed / NORMAL_SPEED;
+    }
+
+    let mut turns = Vec::new();
+    loop {
+        let mut acted = false;
+        for (index, npc) in npcs.iter_mut().enumerate() {
+            if npc.energy >= ACTION_COST {
+                npc.energy -= ACTION_COST;
+                turns.push(index);
+                acted = true;
+            }
+        }
+        if !acted {
+            return turns;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{self, Content};
+    use crate::keybindings::Action;
+    use crate::message_log::Category;
+    use crate::outcome::Interaction;
+    use crate::world::entities::{ItemType, NPCType};
+    use crate::world::Balance;
+
+    fn npc(npc_type: NPCType) -> NPC {
+        NPC {
+            x: 0,
+            y: 0,
+            npc_type,
+            energy: 0,
+        }
+    }
+
+    fn count_turns(npc_type: NPCType, actions: u32) -> usize {
+        let mut npcs = vec![npc(npc_type)];
+        (0..actions)
+            .map(|_| schedule(&mut npcs, ACTION_COST).len())
+            .sum()
+    }
+
+    #[test]
+    fn npcs_act_according_to_their_speed() {
+        assert_eq!(count_turns(NPCType::SPIDER, 10), 10);
+        assert_eq!(count_turns(NPCType::FISH, 10), 15);
+        assert_eq!(count_turns(NPCType::TROLL, 10), 5);
+    }
+
+    #[test]
+    fn npcs_act_in_rounds() {
+        let mut npcs = vec![npc(NPCType::FISH), npc(NPCType::SPIDER), npc(NPCType::FISH)];
+        assert_eq!(schedule(&mut npcs, ACTION_COST), vec![0, 1, 2]);
+        assert_eq!(schedule(&mut npcs, ACTION_COST), vec![0, 1, 2, 0, 2]);
+        assert_eq!(
+            schedule(&mut npcs, 2 * ACTION_COST),
+            vec![0, 1, 2, 0, 1, 2, 0, 2]
+        );
+    }
+
+    #[test]
+    fn schedule_is_deterministic() {
+        let run = || {
+            let mut npcs = vec![
+                npc(NPCType::TROLL),
+                npc(NPCType::FISH),
+                npc(NPCType::SPIDER),
+            ];
+            (0..20)
+                .map(|i| schedule(&mut npcs, elapsed_time(ACTION_COST + i * 10, NORMAL_SPEED)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn slow_actions_give_npcs_more_turns() {
+        let interaction = |cost| {
+            Outcome::Interaction(Interaction {
+                message: "tree-chopped".to_string(),
+                category: Category::Info,
+                item: None,
+                cost,
+                counts: None,
+            })
+        };
+        assert_eq!(action_cost(&interaction(2 * ACTION_COST)), 2 * ACTION_COST);
+        assert_eq!(action_cost(&Outcome::FishIgnored), ACTION_COST);
+        assert_eq!(elapsed_time(ACTION_COST, SWIMMING_SPEED), 2 * ACTION_COST);
+
+        let mut npcs = vec![npc(NPCType::SPIDER)];
+        let chop = action_cost(&interaction(2 * ACTION_COST));
+        assert_eq!(
+            schedule(&mut npcs, elapsed_time(chop, NORMAL_SPEED)).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn entering_water_is_swimming_speed() {
+        let mut world = World::with_seed(1, Balance::default());
+        let (x, y) = (world.player.x + 1, world.player.y);
+        world.terrain.tiles[y as usize * TERRAIN_WIDTH + x as usize] = TileType::WATER;
+        world.player.inventory.push(ItemType::SNORKEL);
+        // a troll acts every other normal turn, so it only acts now if the
+        // turn lasted twice as long
+        world.npcs = vec![npc(NPCType::TROLL)];
+        game::play_turn(&mut world, &Content::default(), Action::MoveEast);
+        assert_eq!((world.player.x, world.player.y), (x, y));
+        assert_eq!(world.npcs[0].energy, 0);
+    }
+}
@@ -0,0 +1,506 @@
+use crate::events::GameEvent;
+use crate::world::entities::{ItemType, NPCType, NPC};
+use crate::world::terrain::{TileType, TERRAIN_HEIGHT, TERRAIN_WIDTH};
+use crate::world::World;
+use rand::Rng;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST, INT};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+// Default directory the scripts are loaded from, every *.rhai file in it
+pub const SCRIPTS_DIR: &str = "scripts";
+// Stops a script stuck in a loop instead of freezing the game
+const MAX_OPERATIONS: u64 = 100_000;
+
+// Scripts react to what happens in the game by defining hook functions, all
+// optional:
+//
+//   on_turn(turn)                      after every turn
+//   on_enter_tile(x, y, tile)          the player stepped on a tile
+//   on_tile_changed(x, y, from, to)
+//   on_npc_killed(npc, x, y)
+//   on_item_acquired(item)
+//   on_player_died()
+//   on_game_won()
+//
+// and change the world with:
+//
+//   tile(x, y) / set_tile(x, y, tile)
+//   npc_at(x, y) / spawn_npc(npc, x, y) / remove_npc(x, y)
+//     (spawn_npc only puts the NPC on a free tile it lives on, and tells
+//     whether it did)
+//   has_item(item) / give_item(item) / take_item(item)
+//   player_x() / player_y() / turn() / random(n)
+//   message(text)
+//
+// Tiles, NPCs and items are named by their ids: "grass", "spider-web",
+// "troll", "harpoon"... For example, scripts/shrine.rhai:
+//
+//   fn on_enter_tile(x, y, tile) {
+//       if tile == "sand" && random(100) < 5 && !has_item("snorkel") {
+//           give_item("snorkel");
+//           message("A snorkel lies half buried in the sand.");
+//       }
+//   }
+struct Script {
+    path: PathBuf,
+    ast: AST,
+}
+
+// What the scripts can reach while their hooks run
+#[derive(Default)]
+struct Host {
+    world: Option<World>,
+    messages: Vec<String>,
+}
+
+// What the hooks of a turn left for the player to read
+#[derive(Default)]
+pub struct Output {
+    pub messages: Vec<String>,
+    // "<script>: <error>" for every hook that failed
+    pub errors: Vec<String>,
+}
+
+pub struct Scripts {
+    engine: Engine,
+    host: Rc<RefCell<Host>>,
+    scripts: Vec<Script>,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        Scripts::new()
+    }
+}
+
+impl Scripts {
+    // No scripts yet, only the engine and the API they get
+    pub fn new() -> Scripts {
+        let host = Rc::new(RefCell::new(Host::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        register_api(&mut engine, &host);
+        Scripts {
+            engine,
+            host,
+            scripts: Vec::new(),
+        }
+    }
+
+    // Compile every script of the directory, in file name order, or none when
+    // there is no such directory.
+    pub fn load(dir: &Path) -> Result<Scripts, String> {
+        let mut scripts = Scripts::new();
+        scripts.add(dir)?;
+        Ok(scripts)
+    }
+
+    // Compile the scripts of another directory, run after the ones already there
+    pub fn add(&mut self, dir: &Path) -> Result<(), String> {
+        if !dir.exists() {
+            return Ok(());
+        }
+        let entries = fs::read_dir(dir).map_err(|error| format!("{}: {}", dir.display(), error))?;
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "rhai")
+            })
+            .collect();
+        paths.sort();
+        for path in paths {
+            let ast = self
+                .engine
+                .compile_file(path.clone())
+                .map_err(|error| format!("{}: {}", path.display(), error))?;
+            self.scripts.push(Script { path, ast });
+        }
+        Ok(())
+    }
+
+    // Call the hooks of every script for the turn that just ended and the
+    // events it recorded. Changes the scripts make go straight to the world.
+    pub fn run(&self, world: &mut World, events: &[GameEvent]) -> Output {
+        let mut output = Output::default();
+        if self.scripts.is_empty() {
+            return output;
+        }
+        self.host.borrow_mut().world = Some(world.clone());
+
+        for script in &self.scripts {
+            let mut call = |hook: &str, args: Vec<Dynamic>| {
+                if let Err(error) = self.call(script, hook, args) {
+                    output
+                        .errors
+                        .push(format!("{}: {}", script.path.display(), error));
+                }
+            };
+            call("on_turn", vec![Dynamic::from(INT::from(world.turn))]);
+            for event in events {
+                match *event {
+                    GameEvent::PlayerMoved { to: (x, y), .. } => {
+                        let tile = world.terrain.tiles[index(x, y)];
+                        call("on_enter_tile", vec![int(x), int(y), tile.id().into()]);
+                    }
+                    GameEvent::TileChanged { x, y, from, to } => call(
+                        "on_tile_changed",
+                        vec![int(x), int(y), from.id().into(), to.id().into()],
+                    ),
+                    GameEvent::NpcKilled { npc_type, x, y } => {
+                        call("on_npc_killed", vec![npc_type.id().into(), int(x), int(y)])
+                    }
+                    GameEvent::ItemAcquired(item) => {
+                        call("on_item_acquired", vec![item.id().into()])
+                    }
+                    GameEvent::PlayerDied => call("on_player_died", Vec::new()),
+                    GameEvent::GameWon => call("on_game_won", Vec::new()),
+                }
+            }
+        }
+
+        let mut host = self.host.borrow_mut();
+        if let Some(changed) = host.world.take() {
+            *world = changed;
+        }
+        output.messages = std::mem::take(&mut host.messages);
+        output
+    }
+
+    // Call the hook if the script defines it
+    fn call(&self, script: &Script, hook: &str, args: Vec<Dynamic>) -> Result<(), String> {
+        let defined = script
+            .ast
+            .iter_functions()
+            .any(|function| function.name == hook && function.params.len() == args.len());
+        if !defined {
+            return Ok(());
+        }
+        // only the hook runs, not the top level statements of the script
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &script.ast, hook, args)
+            .map(|_| ())
+            .map_err(|error| error.to_string())
+    }
+}
+
+fn int(value: i32) -> Dynamic {
+    Dynamic::from(INT::from(value))
+}
+
+fn index(x: i32, y: i32) -> usize {
+    y as usize * TERRAIN_WIDTH + x as usize
+}
+
+// The position if it is on the map
+fn position(x: INT, y: INT) -> Result<(i32, i32), String> {
+    if (0..TERRAIN_WIDTH as INT).contains(&x) && (0..TERRAIN_HEIGHT as INT).contains(&y) {
+        Ok((x as i32, y as i32))
+    } else {
+        Err(format!("({}, {}) is off the map", x, y))
+    }
+}
+
+fn tile_type(id: &str) -> Result<TileType, String> {
+    TileType::from_id(id).ok_or_else(|| format!("unknown tile '{}'", id))
+}
+
+fn npc_type(id: &str) -> Result<NPCType, String> {
+    NPCType::from_id(id).ok_or_else(|| format!("unknown npc '{}'", id))
+}
+
+fn item_type(id: &str) -> Result<ItemType, String> {
+    ItemType::from_id(id).ok_or_else(|| format!("unknown item '{}'", id))
+}
+
+// Run `f` on the world the hooks are called for
+fn with_world<T>(
+    host: &RefCell<Host>,
+    f: impl FnOnce(&mut World) -> Result<T, String>,
+) -> Result<T, Box<EvalAltResult>> {
+    let mut host = host.borrow_mut();
+    let world = host.world.as_mut().ok_or("no game is running")?;
+    f(world).map_err(Into::into)
+}
+
+fn register_api(engine: &mut Engine, host: &Rc<RefCell<Host>>) {
+    let shared = host.clone();
+    engine.register_fn("tile", move |x: INT, y: INT| {
+        with_world(&shared, |world| {
+            let (x, y) = position(x, y)?;
+            Ok(world.terrain.tiles[index(x, y)].id().to_string())
+        })
+    });
+    let shared = host.clone();
+    engine.register_fn("set_tile", move |x: INT, y: INT, tile: &str| {
+        with_world(&shared, |world| {
+            let (x, y) = position(x, y)?;
+            world.set_tile(x, y, tile_type(tile)?);
+            Ok(())
+        })
+    });
+
+    let shared = host.clone();
+    engine.register_fn("npc_at", move |x: INT, y: INT| {
+        with_world(&shared, |world| {
+            let (x, y) = position(x, y)?;
+            Ok(world
+                .npcs
+                .iter()
+                .find(|npc| npc.x == x && npc.y == y)
+                .map_or(Dynamic::UNIT, |npc| npc.npc_type.id().into()))
+        })
+    });
+    let shared = host.clone();
+    engine.register_fn("spawn_npc", move |npc: &str, x: INT, y: INT| {
+        with_world(&shared, |world| {
+            let npc_type = npc_type(npc)?;
+            let (x, y) = position(x, y)?;
+            let free = world.terrain.tiles[index(x, y)] == npc_type.allowed_tile()
+                && (world.player.x, world.player.y) != (x, y)
+                && !world.npcs.iter().any(|npc| npc.x == x && npc.y == y);
+            if free {
+                world.npcs.push(NPC {
+                    x,
+                    y,
+                    npc_type,
+                    energy: 0,
+                });
+            }
+            Ok(free)
+        })
+    });
+    // Take the NPC out of the game without killing it
+    let shared = host.clone();
+    engine.register_fn("remove_npc", move |x: INT, y: INT| {
+        with_world(&shared, |world| {
+            let (x, y) = position(x, y)?;
+            let found = world.npcs.iter().position(|npc| npc.x == x && npc.y == y);
+            if let Some(npc_index) = found {
+                world.npcs.remove(npc_index);
+            }
+            Ok(found.is_some())
+        })
+    });
+
+    let shared = host.clone();
+    engine.register_fn("has_item", move |item: &str| {
+        with_world(&shared, |world| Ok(world.player.has(item_type(item)?)))
+    });
+    let shared = host.clone();
+    engine.register_fn("give_item", move |item: &str| {
+        with_world(&shared, |world| {
+            world.give_item(item_type(item)?);
+            Ok(())
+        })
+    });
+    let shared = host.clone();
+    engine.register_fn("take_item", move |item: &str| {
+        with_world(&shared, |world| {
+            let item = item_type(item)?;
+            let inventory = &mut world.player.inventory;
+            let found = inventory.iter().position(|&other| other == item);
+            if let Some(item_index) = found {
+                inventory.remove(item_index);
+            }
+            Ok(found.is_some())
+        })
+    });
+
+    let shared = host.clone();
+    engine.register_fn("player_x", move || {
+        with_world(&shared, |world| Ok(INT::from(world.player.x)))
+    });
+    let shared = host.clone();
+    engine.register_fn("player_y", move || {
+        with_world(&shared, |world| Ok(INT::from(world.player.y)))
+    });
+    let shared = host.clone();
+    engine.register_fn("turn", move || {
+        with_world(&shared, |world| Ok(INT::from(world.turn)))
+    });
+    // From the game generator, so a seed still always plays out the same way
+    let shared = host.clone();
+    engine.register_fn("random", move |n: INT| {
+        with_world(&shared, |world| {
+            if n <= 0 {
+                return Err(format!("random({}) needs a positive bound", n));
+            }
+            Ok(world.rng.gen_range(0..n))
+        })
+    });
+
+    let shared = host.clone();
+    engine.register_fn("message", move |text: &str| {
+        shared.borrow_mut().messages.push(text.to_string());
+    });
+    // printing would draw over the game screen, it goes to the log as well
+    let shared = host.clone();
+    engine.on_print(move |text| shared.borrow_mut().messages.push(text.to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{self, Content};
+    use crate::keybindings::Action;
+    use crate::world::Balance;
+
+    // Scripts loaded from a directory of their own for the test
+    fn load(test: &str, files: &[(&str, &str)]) -> Result<Scripts, String> {
+        let dir =
+            std::env::temp_dir().join(format!("game-scripts-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (name, source) in files {
+            fs::write(dir.join(name), source).unwrap();
+        }
+        let scripts = Scripts::load(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        scripts
+    }
+
+    // A world with the player at (10, 10), no NPCs and the tiles east of them
+    fn world(east: &[TileType]) -> World {
+        let mut world = World::with_seed(1, Balance::default());
+        (world.player.x, world.player.y) = (10, 10);
+        world.npcs.clear();
+        for (step, &tile) in east.iter().enumerate() {
+            world.terrain.tiles[index(11 + step as i32, 10)] = tile;
+        }
+        world
+    }
+
+    fn content(test: &str, source: &str) -> Content {
+        Content {
+            scripts: load(test, &[("test.rhai", source)]).unwrap(),
+            ..Content::default()
+        }
+    }
+
+    #[test]
+    fn turn_hooks_change_the_world() {
+        let content = content(
+            "turn",
+            "fn on_turn(turn) { set_tile(0, 0, \"castle\"); message(`turn ${turn}`); }",
+        );
+        let mut world = world(&[]);
+        let turn = game::play_turn(&mut world, &content, Action::Wait);
+        assert!(world.terrain.tiles[0] == TileType::CASTLE);
+        assert_eq!(turn.output.messages, ["turn 1"]);
+        assert!(turn.output.errors.is_empty());
+        // what the scripts changed is an event of the turn too
+        assert!(turn.events.iter().any(|event| matches!(
+            event,
+            GameEvent::TileChanged { x: 0, y: 0, to, .. } if *to == TileType::CASTLE
+        )));
+    }
+
+    #[test]
+    fn enter_hooks_give_items() {
+        let content = content(
+            "enter",
+            "fn on_enter_tile(x, y, tile) { if tile == \"sand\" { give_item(\"snorkel\"); message(`${x},${y}`); } }",
+        );
+        let mut world = world(&[TileType::SAND]);
+        let turn = game::play_turn(&mut world, &content, Action::MoveEast);
+        assert!(world.player.has(ItemType::SNORKEL));
+        assert_eq!(turn.output.messages, ["11,10"]);
+        assert!(turn.events.iter().any(
+            |event| matches!(event, GameEvent::ItemAcquired(item) if *item == ItemType::SNORKEL)
+        ));
+    }
+
+    #[test]
+    fn kill_hooks_spawn_npcs_where_they_live() {
+        let content = content(
+            "kill",
+            "fn on_npc_killed(npc, x, y) {
+                 let spawned = [
+                     spawn_npc(\"troll\", x + 1, y),
+                     spawn_npc(\"troll\", x + 1, y),
+                     spawn_npc(\"fish\", x + 2, y),
+                     spawn_npc(\"fish\", x + 3, y),
+                     spawn_npc(\"troll\", x, y),
+                 ];
+                 message(`${npc} ${spawned}`);
+             }",
+        );
+        let mut world = world(&[
+            TileType::GRASS,
+            TileType::GRASS,
+            TileType::GRASS,
+            TileType::WATER,
+        ]);
+        world.npcs.push(NPC {
+            x: 11,
+            y: 10,
+            npc_type: NPCType::SPIDER,
+            energy: 0,
+        });
+        let turn = game::play_turn(&mut world, &content, Action::MoveEast);
+        // a troll on the grass, a fish in the water, and nothing on a taken
+        // tile or one the NPC does not live on
+        assert_eq!(
+            turn.output.messages,
+            ["spider [true, false, false, true, false]"]
+        );
+        let npcs: Vec<(i32, i32, NPCType)> = world
+            .npcs
+            .iter()
+            .map(|npc| (npc.x, npc.y, npc.npc_type))
+            .collect();
+        assert!(npcs == [(12, 10, NPCType::TROLL), (14, 10, NPCType::FISH)]);
+    }
+
+    #[test]
+    fn scripts_that_do_not_compile_are_refused() {
+        let error = load("compile", &[("broken.rhai", "fn on_turn(turn) {")])
+            .err()
+            .unwrap();
+        assert!(error.contains("broken.rhai"));
+    }
+
+    #[test]
+    fn failing_hooks_are_reported_and_the_others_still_run() {
+        let scripts = load(
+            "hooks",
+            &[
+                ("a.rhai", "fn on_turn(turn) { set_tile(-1, 0, \"grass\"); }"),
+                ("b.rhai", "fn on_turn(turn) { give_item(\"crown\"); }"),
+                ("c.rhai", "fn on_turn(turn) { message(\"still here\"); }"),
+            ],
+        )
+        .unwrap();
+        let mut world = World::with_seed(1, Balance::default());
+        let output = scripts.run(&mut world, &[]);
+        assert_eq!(output.errors.len(), 2);
+        assert!(output.errors[0].contains("a.rhai") && output.errors[0].contains("off the map"));
+        assert!(output.errors[1].contains("b.rhai") && output.errors[1].contains("unknown item"));
+        assert_eq!(output.messages, vec!["still here".to_string()]);
+    }
+
+    #[test]
+    fn endless_hooks_are_stopped() {
+        let scripts = load("loop", &[("loop.rhai", "fn on_turn(turn) { loop {} }")]).unwrap();
+        let mut world = World::with_seed(1, Balance::default());
+        let output = scripts.run(&mut world, &[]);
+        assert_eq!(output.errors.len(), 1);
+    }
+
+    #[test]
+    fn hooks_with_other_parameters_are_not_called() {
+        let scripts = load(
+            "arity",
+            &[("arity.rhai", "fn on_turn() { throw \"called\"; }")],
+        )
+        .unwrap();
+        let mut world = World::with_seed(1, Balance::default());
+        let output = scripts.run(&mut world, &[]);
+        assert!(output.errors.is_empty());
+    }
+}
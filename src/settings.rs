@@ -0,0 +1,45 @@
+use crate::keybindings::Preset;
+use crate::tileset::Tileset;
+use crate::world::Difficulty;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+// settings.toml, written by the settings screen of the main menu
+//
+//   tileset = "ascii"
+//   keybindings = "vi"
+//   difficulty = "hard"
+//   speedrun_timer = true
+//   player_name = "alk"
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub tileset: Tileset,
+    // preset used when the keybindings file does not pick one
+    pub keybindings: Preset,
+    pub difficulty: Difficulty,
+    pub speedrun_timer: bool,
+    // asked for before the first game
+    pub player_name: String,
+}
+
+impl Settings {
+    // Load the settings, or the default ones when there is no such file.
+    pub fn load(path: &Path) -> Result<Settings, String> {
+        if !path.exists() {
+            return Ok(Settings::default());
+        }
+        let content = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        toml::from_str(&content).map_err(|error| format!("{}: {}", path.display(), error))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        let content = toml::to_string(self)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::write(path, content)
+    }
+}
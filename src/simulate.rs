@@ -0,0 +1,251 @@
+use crate::bot;
+use crate::events::GameEvent;
+use crate::game::{self, Content, Turn};
+use crate::gym::ACTIONS;
+use crate::keybindings::Action;
+use crate::world::entities::{ItemType, NPCType};
+use crate::world::terrain::{TileType, TERRAIN_WIDTH};
+use crate::world::{Balance, World};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::io::{self, Write};
+
+// Many games played headless by a bot, written as CSV for the balancing
+// spreadsheets, with --simulate:
+//
+//   game --simulate 1000 --policy solver --difficulty hard > runs.csv
+//   game --simulate 1000 --summary > summary.csv
+//
+// The runs give a row per game, the summary the distribution of every figure
+// over the games.
+
+// How the bot of the simulation picks its moves
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Policy {
+    // the planner of the bot module, which goes for the castle
+    Solver,
+    // any move or waiting, all as likely
+    Random,
+}
+
+impl Policy {
+    pub fn from_id(id: &str) -> Option<Policy> {
+        match id {
+            "solver" => Some(Policy::Solver),
+            "random" => Some(Policy::Random),
+            _ => None,
+        }
+    }
+}
+
+// What happened in a game
+pub struct Run {
+    pub seed: u64,
+    pub won: bool,
+    // message id of the move the player died on, "waiting" when it was not one
+    pub death: Option<String>,
+    pub turns: u32,
+    // turns from getting the axe to uncovering the chest
+    pub chest_delay: Option<u32>,
+    // trees chopped when the chest was uncovered, the last one included
+    pub chest_chops: Option<u32>,
+    // how many times the player ran into an NPC of each type, by NPCType::index
+    pub encounters: Vec<u32>,
+}
+
+// Play the world of the seed until it is won, lost or `max_turns` are played
+pub fn simulate(
+    seed: u64,
+    policy: Policy,
+    balance: Balance,
+    content: &Content,
+    max_turns: u32,
+) -> Run {
+    let mut world = World::with_seed(seed, balance);
+    // not the world's, so the random bot does not change how the game plays
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut run = Run {
+        seed,
+        won: false,
+        death: None,
+        turns: 0,
+        chest_delay: None,
+        chest_chops: None,
+        encounters: vec![0; NPCType::all().count()],
+    };
+    let mut axe_at = world.player.has(ItemType::AXE).then_some(0);
+
+    while !world.player.dead && world.stats.won_at.is_none() && world.turn < max_turns {
+        let action = match policy {
+            Policy::Solver => bot::next_action(&world),
+            Policy::Random => *ACTIONS.choose(&mut rng).unwrap(),
+        };
+        if let Some(npc_type) = met(&world, action) {
+            run.encounters[npc_type.index()] += 1;
+        }
+        let Turn {
+            outcome, events, ..
+        } = game::play_turn(&mut world, content, action);
+
+        if axe_at.is_none() && world.player.has(ItemType::AXE) {
+            axe_at = Some(world.turn);
+        }
+        let uncovered = events.iter().any(
+            |event| matches!(event, GameEvent::TileChanged { to, .. } if to.like() == TileType::CHEST),
+        );
+        if uncovered && run.chest_delay.is_none() {
+            run.chest_delay = axe_at.map(|turn| world.turn - turn);
+            run.chest_chops = Some(world.stats.trees_chopped);
+        }
+        if world.player.dead {
+            run.death = Some(outcome.map_or("waiting".to_string(), |outcome| {
+                outcome.message_id().to_string()
+            }));
+        }
+    }
+    run.won = world.stats.won_at.is_some();
+    run.turns = world.turn;
+    run
+}
+
+// The NPC the move runs into, met the way move_player meets them
+fn met(world: &World, action: Action) -> Option<NPCType> {
+    let (dx, dy) = action.direction()?;
+    let (x, y) = (world.player.x + dx, world.player.y + dy);
+    let npc = world.npcs.iter().find(|npc| npc.x == x && npc.y == y)?;
+    let tile = world.terrain.tiles[y as usize * TERRAIN_WIDTH + x as usize];
+    (npc.npc_type.allowed_tile() == tile).then_some(npc.npc_type)
+}
+
+// A row per game
+pub fn write_runs(output: &mut impl Write, runs: &[Run]) -> Result<(), io::Error> {
+    let npcs: Vec<String> = NPCType::all()
+        .map(|npc_type| format!("met_{}", npc_type.id()))
+        .collect();
+    writeln!(
+        output,
+        "seed,won,death,turns,chest_delay,chest_chops,{}",
+        npcs.join(",")
+    )?;
+    let optional = |value: Option<u32>| value.map_or(String::new(), |value| value.to_string());
+    for run in runs {
+        let encounters: Vec<String> = run.encounters.iter().map(u32::to_string).collect();
+        writeln!(
+            output,
+            "{},{},{},{},{},{},{}",
+            run.seed,
+            run.won,
+            run.death.as_deref().unwrap_or(""),
+            run.turns,
+            optional(run.chest_delay),
+            optional(run.chest_chops),
+            encounters.join(",")
+        )?;
+    }
+    Ok(())
+}
+
+// A row per figure: how many games it was measured in and its distribution.
+// Deaths are counted by cause, their mean being the death rate.
+pub fn write_summary(output: &mut impl Write, runs: &[Run]) -> Result<(), io::Error> {
+    writeln!(output, "metric,count,mean,min,p25,median,p75,p90,max")?;
+    let mut write = |metric: &str, mut values: Vec<u32>| {
+        values.sort_unstable();
+        let count = values.len();
+        if count == 0 {
+            return writeln!(output, "{},0,,,,,,,", metric);
+        }
+        let mean = values.iter().map(|&value| value as f64).sum::<f64>() / count as f64;
+        let at = |percent: usize| values[(count - 1) * percent / 100];
+        writeln!(
+            output,
+            "{},{},{:.2},{},{},{},{},{},{}",
+            metric,
+            count,
+            mean,
+            at(0),
+            at(25),
+            at(50),
+            at(75),
+            at(90),
+            at(100)
+        )
+    };
+    let won = runs.iter().filter(|run| run.won);
+    write("turns_to_win", won.map(|run| run.turns).collect())?;
+    write(
+        "chest_delay",
+        runs.iter().filter_map(|run| run.chest_delay).collect(),
+    )?;
+    write(
+        "chest_chops",
+        runs.iter().filter_map(|run| run.chest_chops).collect(),
+    )?;
+    for npc_type in NPCType::all() {
+        write(
+            &format!("met_{}", npc_type.id()),
+            runs.iter()
+                .map(|run| run.encounters[npc_type.index()])
+                .collect(),
+        )?;
+    }
+
+    let mut causes: Vec<&str> = runs.iter().filter_map(|run| run.death.as_deref()).collect();
+    causes.sort_unstable();
+    causes.dedup();
+    for cause in causes {
+        let deaths = runs
+            .iter()
+            .map(|run| u32::from(run.death.as_deref() == Some(cause)))
+            .collect();
+        write(&format!("deaths_{}", cause), deaths)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_record_the_game() {
+        let content = Content::default();
+        let runs: Vec<Run> = (1..4)
+            .map(|seed| simulate(seed, Policy::Solver, Balance::default(), &content, 500))
+            .collect();
+        for run in &runs {
+            assert!(run.won && run.death.is_none());
+            assert!(run.chest_delay.unwrap() > 0 && run.chest_chops.unwrap() > 0);
+            assert!(run.encounters[NPCType::TROLL.index()] >= 1);
+            assert!(run.encounters[NPCType::FISH.index()] >= 1);
+        }
+
+        let mut csv = Vec::new();
+        write_runs(&mut csv, &runs).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.starts_with("seed,won,death,turns,chest_delay,chest_chops,met_fish"));
+    }
+
+    #[test]
+    fn summaries_give_death_rates() {
+        let content = Content::default();
+        let runs: Vec<Run> = (0..20)
+            .map(|seed| simulate(seed, Policy::Random, Balance::default(), &content, 300))
+            .collect();
+        let mut csv = Vec::new();
+        write_summary(&mut csv, &runs).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+        assert!(rows.iter().all(|row| row.len() == 9));
+        let deaths: usize = runs.iter().filter(|run| run.death.is_some()).count();
+        let counted: usize = rows
+            .iter()
+            .filter(|row| row[0].starts_with("deaths_"))
+            .map(|row| row[2].parse::<f64>().unwrap() * 20.0)
+            .sum::<f64>()
+            .round() as usize;
+        assert_eq!(counted, deaths);
+    }
+}
@@ -0,0 +1,371 @@
+use crate::events::GameEvent;
+use crate::i18n::Catalog;
+use crate::world::entities::{ItemType, NPCType};
+use crate::world::terrain::TileType;
+use crate::world::{Difficulty, World};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// Default location of the personal best splits
+pub const SPLITS_FILE: &str = "splits.json";
+
+// Milestones of a run, in the order they usually happen
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Split {
+    FirstTroll,
+    Axe,
+    Chest,
+    Harpoon,
+    Key,
+    Castle,
+}
+
+impl Split {
+    pub fn message_id(self) -> &'static str {
+        match self {
+            Split::FirstTroll => "split-first-troll",
+            Split::Axe => "split-axe",
+            Split::Chest => "split-chest",
+            Split::Harpoon => "split-harpoon",
+            Split::Key => "split-key",
+            Split::Castle => "split-castle",
+        }
+    }
+
+    // The milestone the event reaches, if any. The types of the mods count
+    // for the built-in ones they are like.
+    fn reached_by(event: &GameEvent) -> Option<Split> {
+        match *event {
+            GameEvent::NpcKilled { npc_type, .. } if npc_type.like() == NPCType::TROLL => {
+                Some(Split::FirstTroll)
+            }
+            GameEvent::TileChanged { to, .. } if to.like() == TileType::CHEST => Some(Split::Chest),
+            GameEvent::ItemAcquired(item) => match item.like() {
+                ItemType::AXE => Some(Split::Axe),
+                ItemType::HARPOON => Some(Split::Harpoon),
+                ItemType::KEY => Some(Split::Key),
+                _ => None,
+            },
+            GameEvent::GameWon => Some(Split::Castle),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct SplitTime {
+    pub split: Split,
+    // real time since the start of the run
+    pub millis: u64,
+    pub turn: u32,
+}
+
+// The fastest won run on a seed and difficulty
+#[derive(Serialize, Deserialize)]
+struct PersonalBest {
+    seed: u64,
+    difficulty: Difficulty,
+    splits: Vec<SplitTime>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PersonalBests {
+    runs: Vec<PersonalBest>,
+}
+
+impl PersonalBests {
+    // Load the personal bests, or none when there is no such file.
+    pub fn load(path: &Path) -> Result<PersonalBests, io::Error> {
+        if !path.exists() {
+            return Ok(PersonalBests::default());
+        }
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), error),
+            )
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)
+    }
+
+    fn get(&self, seed: u64, difficulty: Difficulty) -> Option<&PersonalBest> {
+        self.runs
+            .iter()
+            .find(|run| run.seed == seed && run.difficulty == difficulty)
+    }
+
+    // Keep the splits of a won run if it beats the personal best, and tell whether it did
+    fn record(&mut self, seed: u64, difficulty: Difficulty, splits: &[SplitTime]) -> bool {
+        let Some(finish) = final_time(splits) else {
+            return false;
+        };
+        let run = PersonalBest {
+            seed,
+            difficulty,
+            splits: splits.to_vec(),
+        };
+        match self
+            .runs
+            .iter_mut()
+            .find(|run| run.seed == seed && run.difficulty == difficulty)
+        {
+            Some(best) if final_time(&best.splits).is_some_and(|best| best <= finish) => false,
+            Some(best) => {
+                *best = run;
+                true
+            }
+            None => {
+                self.runs.push(run);
+                true
+            }
+        }
+    }
+}
+
+fn final_time(splits: &[SplitTime]) -> Option<u64> {
+    splits
+        .iter()
+        .find(|time| time.split == Split::Castle)
+        .map(|time| time.millis)
+}
+
+// Times a run from its first turn, comparing its splits with the personal best
+// on the same seed and difficulty
+pub struct Timer {
+    started: Instant,
+    seed: u64,
+    difficulty: Difficulty,
+    splits: Vec<SplitTime>,
+    best: Vec<SplitTime>,
+}
+
+impl Timer {
+    pub fn start(world: &World, bests: &PersonalBests) -> Timer {
+        let difficulty = world.balance.difficulty;
+        Timer {
+            started: Instant::now(),
+            seed: world.seed,
+            difficulty,
+            splits: Vec::new(),
+            best: bests
+                .get(world.seed, difficulty)
+                .map_or(Vec::new(), |best| best.splits.clone()),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    // Record the milestone the event reaches, when it is reached for the first time
+    pub fn record(&mut self, event: &GameEvent, turn: u32) -> Option<SplitTime> {
+        let split = Split::reached_by(event)?;
+        if self.splits.iter().any(|time| time.split == split) {
+            return None;
+        }
+        let time = SplitTime {
+            split,
+            millis: self.elapsed().as_millis() as u64,
+            turn,
+        };
+        self.splits.push(time);
+        Some(time)
+    }
+
+    // Store the run if it is a new personal best, and tell whether it is
+    pub fn finish(&self, bests: &mut PersonalBests) -> bool {
+        bests.record(self.seed, self.difficulty, &self.splits)
+    }
+
+    // How the split compares with the personal best, as "+1.2" or "-0.4"
+    pub fn delta(&self, time: SplitTime) -> Option<String> {
+        let best = self.best.iter().find(|best| best.split == time.split)?;
+        let delta = time.millis as i64 - best.millis as i64;
+        let (color, sign) = if delta <= 0 {
+            ("\x1b[92m", '-')
+        } else {
+            ("\x1b[91m", '+')
+        };
+        let delta = delta.unsigned_abs();
+        Some(format!(
+            "{}{}{}.{}\x1b[0m",
+            color,
+            sign,
+            delta / 1000,
+            delta % 1000 / 100
+        ))
+    }
+
+    // The running time and the latest split, for the HUD
+    pub fn hud(&self, catalog: &Catalog) -> String {
+        let mut hud = format_time(self.elapsed().as_millis() as u64);
+        if let Some(&last) = self.splits.last() {
+            hud.push_str(&format!(
+                "   {} {}",
+                catalog.message(last.split.message_id(), &[]),
+                format_time(last.millis)
+            ));
+            if let Some(delta) = self.delta(last) {
+                hud.push_str(&format!(" {}", delta));
+            }
+        }
+        hud
+    }
+}
+
+// mm:ss.t
+pub fn format_time(millis: u64) -> String {
+    format!(
+        "{:02}:{:02}.{}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000 / 100
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::registry::{self, ItemDef, NpcDef, TileDef};
+    use crate::world::{Balance, Registry};
+
+    fn time(split: Split, millis: u64) -> SplitTime {
+        SplitTime {
+            split,
+            millis,
+            turn: 0,
+        }
+    }
+
+    fn timer(best: Vec<SplitTime>) -> Timer {
+        let world = World::with_seed(1, Balance::default());
+        let mut timer = Timer::start(&world, &PersonalBests::default());
+        timer.best = best;
+        timer
+    }
+
+    #[test]
+    fn splits_are_reached_once() {
+        let mut timer = timer(Vec::new());
+        let troll = GameEvent::NpcKilled {
+            npc_type: NPCType::TROLL,
+            x: 1,
+            y: 1,
+        };
+        assert!(timer.record(&troll, 3).is_some_and(|time| time.turn == 3));
+        assert!(timer.record(&troll, 9).is_none());
+        assert!(timer
+            .record(&GameEvent::ItemAcquired(ItemType::SWORD), 10)
+            .is_none());
+        let castle = timer.record(&GameEvent::GameWon, 12).unwrap();
+        assert!(castle.split == Split::Castle);
+        assert!(timer
+            .splits
+            .iter()
+            .map(|time| time.split)
+            .eq([Split::FirstTroll, Split::Castle]));
+        assert!(timer.splits[0].millis <= castle.millis);
+    }
+
+    #[test]
+    fn types_of_the_mods_reach_the_splits_of_theirs() {
+        let _restore = registry::Restore::current();
+        let mut types = Registry::built_in();
+        types.add_item(ItemDef {
+            id: "machete".to_string(),
+            like: ItemType::AXE,
+            glyph: "🔪".to_string(),
+            ascii: "!".to_string(),
+        });
+        types.add_npc(NpcDef {
+            id: "wolf".to_string(),
+            like: NPCType::TROLL,
+            glyph: "🐺".to_string(),
+            ascii: "w".to_string(),
+            speed: 100,
+            lives_on: TileType::GRASS,
+            count: 0,
+        });
+        types
+            .add_tile(TileDef {
+                id: "crate".to_string(),
+                like: TileType::CHEST,
+                glyph: "📦".to_string(),
+                ascii: "=".to_string(),
+                map_char: '=',
+            })
+            .unwrap();
+        registry::install(types);
+
+        let mut timer = timer(Vec::new());
+        let wolf = GameEvent::NpcKilled {
+            npc_type: NPCType::from_id("wolf").unwrap(),
+            x: 1,
+            y: 1,
+        };
+        let machete = GameEvent::ItemAcquired(ItemType::from_id("machete").unwrap());
+        let crate_found = GameEvent::TileChanged {
+            x: 2,
+            y: 2,
+            from: TileType::TREE,
+            to: TileType::from_id("crate").unwrap(),
+        };
+        assert!(timer.record(&wolf, 1).unwrap().split == Split::FirstTroll);
+        assert!(timer.record(&machete, 2).unwrap().split == Split::Axe);
+        assert!(timer.record(&crate_found, 3).unwrap().split == Split::Chest);
+    }
+
+    #[test]
+    fn splits_compare_with_the_best() {
+        let timer = timer(vec![time(Split::Axe, 10_000)]);
+        assert_eq!(
+            timer.delta(time(Split::Axe, 11_250)).unwrap(),
+            "\x1b[91m+1.2\x1b[0m"
+        );
+        assert_eq!(
+            timer.delta(time(Split::Axe, 9_600)).unwrap(),
+            "\x1b[92m-0.4\x1b[0m"
+        );
+        assert!(timer.delta(time(Split::Key, 1_000)).is_none());
+    }
+
+    #[test]
+    fn only_faster_won_runs_are_kept() {
+        let mut bests = PersonalBests::default();
+        let run = |finish| [time(Split::Axe, 1_000), time(Split::Castle, finish)];
+        // lost runs have no time to beat
+        assert!(!bests.record(1, Difficulty::Normal, &[time(Split::Axe, 500)]));
+        assert!(bests.record(1, Difficulty::Normal, &run(60_000)));
+        assert!(!bests.record(1, Difficulty::Normal, &run(60_000)));
+        assert!(!bests.record(1, Difficulty::Normal, &run(70_000)));
+        assert!(bests.record(1, Difficulty::Normal, &run(50_000)));
+        // every seed and difficulty has its own
+        assert!(bests.record(2, Difficulty::Normal, &run(90_000)));
+        assert!(bests.record(1, Difficulty::Hard, &run(90_000)));
+
+        let path = std::env::temp_dir().join(format!("game-splits-{}.json", std::process::id()));
+        bests.save(&path).unwrap();
+        let loaded = PersonalBests::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let best = loaded.get(1, Difficulty::Normal).unwrap();
+        assert_eq!(final_time(&best.splits), Some(50_000));
+        assert_eq!(loaded.runs.len(), 3);
+    }
+
+    #[test]
+    fn times_are_formatted() {
+        assert_eq!(format_time(0), "00:00.0");
+        assert_eq!(format_time(61_250), "01:01.2");
+        assert_eq!(format_time(3_599_999), "59:59.9");
+    }
+}
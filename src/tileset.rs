@@ -0,0 +1,78 @@
+use crate::world::entities::{ItemType, NPCType};
+use crate::world::registry::registry;
+use crate::world::terrain::TileType;
+use crate::world::weather::Weather;
+use serde::{Deserialize, Serialize};
+
+// How the world is drawn. Emoji glyphs take two terminal columns, the ASCII
+// ones a single coloured character, for terminals without emoji support.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tileset {
+    #[default]
+    Emoji,
+    Ascii,
+}
+
+impl Tileset {
+    pub const ALL: [Tileset; 2] = [Tileset::Emoji, Tileset::Ascii];
+
+    pub fn message_id(self) -> &'static str {
+        match self {
+            Tileset::Emoji => "tileset-emoji",
+            Tileset::Ascii => "tileset-ascii",
+        }
+    }
+
+    pub fn tile(self, tile_type: TileType) -> &'static str {
+        let def = registry().tile(tile_type);
+        match self {
+            Tileset::Emoji => &def.glyph,
+            Tileset::Ascii => &def.ascii,
+        }
+    }
+
+    pub fn npc(self, npc_type: NPCType) -> &'static str {
+        let def = registry().npc(npc_type);
+        match self {
+            Tileset::Emoji => &def.glyph,
+            Tileset::Ascii => &def.ascii,
+        }
+    }
+
+    pub fn player(self) -> &'static str {
+        match self {
+            Tileset::Emoji => "🏃",
+            Tileset::Ascii => "\x1b[1m@\x1b[22m",
+        }
+    }
+
+    pub fn item(self, item: ItemType) -> &'static str {
+        let def = registry().item(item);
+        match self {
+            Tileset::Emoji => &def.glyph,
+            Tileset::Ascii => &def.ascii,
+        }
+    }
+
+    // Glyph the weather draws over the map
+    pub fn weather(self, weather: Weather) -> Option<&'static str> {
+        match (self, weather) {
+            (_, Weather::Clear) => None,
+            (Tileset::Emoji, Weather::Rain) => Some("☔"),
+            (Tileset::Emoji, Weather::Fog) => Some("🌫️ "),
+            (Tileset::Emoji, Weather::Storm) => Some("⚡"),
+            (Tileset::Ascii, Weather::Rain) => Some("\x1b[94m'\x1b[39m"),
+            (Tileset::Ascii, Weather::Fog) => Some("\x1b[90m%\x1b[39m"),
+            (Tileset::Ascii, Weather::Storm) => Some("\x1b[93m!\x1b[39m"),
+        }
+    }
+
+    // What is drawn where the player cannot see
+    pub fn hidden(self) -> &'static str {
+        match self {
+            Tileset::Emoji => "  ",
+            Tileset::Ascii => " ",
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::world::terrain::{TERRAIN_HEIGHT, TERRAIN_WIDTH};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// Default location of the balance config, loaded when present
+pub const BALANCE_FILE: &str = "balance.toml";
+// More NPCs than that would crowd the map
+const MAX_NPC_COUNT: usize = 50;
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn message_id(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "difficulty-easy",
+            Difficulty::Normal => "difficulty-normal",
+            Difficulty::Hard => "difficulty-hard",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Difficulty> {
+        match id {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+// balance.toml: pick a preset (the difficulty from the settings when left out),
+// then override any of its values
+//
+//   preset = "hard"
+//   troll_count = 12
+//   web_chance = 25
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Balance {
+    // the preset these values started from
+    pub difficulty: Difficulty,
+    pub fish_count: usize,
+    pub troll_count: usize,
+    pub spider_count: usize,
+    // chance in percent, every turn, that an NPC stays still
+    pub idle_chance: u32,
+    // chance in percent, every turn, that a spider weaves a web, doubled at night
+    pub web_chance: u32,
+    // chance in percent that a chopped tree hides the chest
+    pub chest_chance: u32,
+    pub forest_radius: usize,
+    pub pond_radius: usize,
+}
+
+impl Default for Balance {
+    fn default() -> Self {
+        Balance::preset(Difficulty::default())
+    }
+}
+
+impl Balance {
+    pub fn preset(difficulty: Difficulty) -> Balance {
+        match difficulty {
+            Difficulty::Easy => Balance {
+                difficulty,
+                fish_count: 4,
+                troll_count: 3,
+                spider_count: 1,
+                idle_chance: 20,
+                web_chance: 10,
+                chest_chance: 25,
+                forest_radius: 10,
+                pond_radius: 15,
+            },
+            Difficulty::Normal => Balance {
+                difficulty,
+                fish_count: 3,
+                troll_count: 5,
+                spider_count: 1,
+                idle_chance: 10,
+                web_chance: 15,
+                chest_chance: 15,
+                forest_radius: 10,
+                pond_radius: 15,
+            },
+            Difficulty::Hard => Balance {
+                difficulty,
+                fish_count: 2,
+                troll_count: 8,
+                spider_count: 2,
+                idle_chance: 5,
+                web_chance: 20,
+                chest_chance: 10,
+                forest_radius: 12,
+                pond_radius: 16,
+            },
+        }
+    }
+
+    // Load the balance config, or the given preset when there is no such file.
+    pub fn load(path: &Path, difficulty: Difficulty) -> Result<Balance, String> {
+        if !path.exists() {
+            return Ok(Balance::preset(difficulty));
+        }
+        let content = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        Balance::parse(&content, difficulty)
+            .map_err(|message| format!("{}: {}", path.display(), message))
+    }
+
+    pub fn parse(content: &str, difficulty: Difficulty) -> Result<Balance, String> {
+        let mut overrides: toml::Table =
+            toml::from_str(content).map_err(|error| error.to_string())?;
+        let difficulty = match overrides.remove("preset") {
+            Some(preset) => preset
+                .as_str()
+                .and_then(Difficulty::from_id)
+                .ok_or(format!("unknown preset {}", preset))?,
+            None => difficulty,
+        };
+        if overrides.contains_key("difficulty") {
+            return Err("use 'preset' to pick the difficulty".to_string());
+        }
+
+        let mut values = toml::Table::try_from(Balance::preset(difficulty))
+            .map_err(|error| error.to_string())?;
+        values.extend(overrides);
+        let balance: Balance = values.try_into().map_err(|error| error.to_string())?;
+        balance.validate()?;
+        Ok(balance)
+    }
+
+    // One of the chances by its name in the config, for the interaction rules
+    pub fn chance(&self, name: &str) -> Option<u32> {
+        match name {
+            "idle_chance" => Some(self.idle_chance),
+            "web_chance" => Some(self.web_chance),
+            "chest_chance" => Some(self.chest_chance),
+            _ => None,
+        }
+    }
+
+    // Check the values give a world that can be generated and won
+    pub fn validate(&self) -> Result<(), String> {
+        // every kind of NPC carries an item the player needs
+        for (name, count) in [
+            ("fish_count", self.fish_count),
+            ("troll_count", self.troll_count),
+            ("spider_count", self.spider_count),
+        ] {
+            if !(1..=MAX_NPC_COUNT).contains(&count) {
+                return Err(format!("{} must be between 1 and {}", name, MAX_NPC_COUNT));
+            }
+        }
+        for (name, chance) in [
+            ("idle_chance", self.idle_chance),
+            ("web_chance", self.web_chance),
+        ] {
+            if chance > 100 {
+                return Err(format!("{} must be between 0 and 100", name));
+            }
+        }
+        // the chest holds the harpoon
+        if !(1..=100).contains(&self.chest_chance) {
+            return Err("chest_chance must be between 1 and 100".to_string());
+        }
+
+        // the forest and the pond must fit on the map, next to each other
+        let max_forest_radius = (TERRAIN_HEIGHT - 4) / 2;
+        if !(1..=max_forest_radius).contains(&self.forest_radius) {
+            return Err(format!(
+                "forest_radius must be between 1 and {}",
+                max_forest_radius
+            ));
+        }
+        let max_pond_radius = (TERRAIN_HEIGHT - 8) / 2;
+        if !(3..=max_pond_radius).contains(&self.pond_radius) {
+            return Err(format!(
+                "pond_radius must be between 3 and {}",
+                max_pond_radius
+            ));
+        }
+        if 2 * self.pond_radius + self.forest_radius >= TERRAIN_WIDTH / 2 - 4 {
+            return Err(format!(
+                "the pond and the forest do not fit side by side, keep 2 * pond_radius + forest_radius under {}",
+                TERRAIN_WIDTH / 2 - 4
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+// In-game time advances with every turn the player takes.
+pub const MINUTES_PER_TURN: u32 = 5;
+const MINUTES_PER_DAY: u32 = 24 * 60;
+// The adventure starts in the morning
+const START_MINUTE: u32 = 8 * 60;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum DayPhase {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl DayPhase {
+    // How far the player sees, in tiles. None means the whole map is visible.
+    pub fn sight_radius(self) -> Option<u32> {
+        match self {
+            DayPhase::Day => None,
+            DayPhase::Dawn | DayPhase::Dusk => Some(18),
+            DayPhase::Night => Some(7),
+        }
+    }
+
+    // Background colour the map is tinted with
+    pub fn tint(self) -> Option<&'static str> {
+        match self {
+            DayPhase::Day => None,
+            DayPhase::Dawn => Some("\x1b[48;5;223m"),
+            DayPhase::Dusk => Some("\x1b[48;5;96m"),
+            DayPhase::Night => Some("\x1b[48;5;17m"),
+        }
+    }
+
+    pub fn message_id(self) -> &'static str {
+        match self {
+            DayPhase::Dawn => "phase-dawn",
+            DayPhase::Day => "phase-day",
+            DayPhase::Dusk => "phase-dusk",
+            DayPhase::Night => "phase-night",
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Clock {
+    // minutes since midnight
+    minute_of_day: u32,
+}
+
+impl Clock {
+    pub fn at_turn(turn: u32) -> Clock {
+        Clock {
+            minute_of_day: (START_MINUTE + turn * MINUTES_PER_TURN) % MINUTES_PER_DAY,
+        }
+    }
+
+    pub fn hour(self) -> u32 {
+        self.minute_of_day / 60
+    }
+
+    pub fn minute(self) -> u32 {
+        self.minute_of_day % 60
+    }
+
+    pub fn phase(self) -> DayPhase {
+        match self.hour() {
+            5..=6 => DayPhase::Dawn,
+            7..=17 => DayPhase::Day,
+            18..=19 => DayPhase::Dusk,
+            _ => DayPhase::Night,
+        }
+    }
+
+    pub fn format(self) -> String {
+        format!("{:02}:{:02}", self.hour(), self.minute())
+    }
+}
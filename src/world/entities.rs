@@ -1,5 +1,9 @@
+use crate::world::registry::registry;
 use crate::world::terrain::TileType;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Player {
     pub x: i32,
     pub y: i32,
@@ -8,10 +12,19 @@ pub struct Player {
     pub dead: bool,
 }
 
+impl Player {
+    pub fn has(&self, item: ItemType) -> bool {
+        self.inventory.iter().any(|held| held.is(item))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct NPC {
     pub x: i32,
     pub y: i32,
     pub npc_type: NPCType,
+    // spent to take turns, see scheduler::schedule
+    pub energy: u32,
 }
 
 pub struct Chest {
@@ -19,29 +32,112 @@ pub struct Chest {
     pub y: i32,
 }
 
-#[derive(PartialEq, Eq)]
-pub enum ItemType {
-    Sword,
-    Axe,
-    Harpoon,
-    Snorkel,
-    Key,
-}
+// Item and NPC types of the registry, like TileType
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ItemType(pub(super) u16);
+
+impl ItemType {
+    pub const SWORD: ItemType = ItemType(0);
+    pub const AXE: ItemType = ItemType(1);
+    pub const HARPOON: ItemType = ItemType(2);
+    pub const SNORKEL: ItemType = ItemType(3);
+    pub const KEY: ItemType = ItemType(4);
+
+    pub fn all() -> impl Iterator<Item = ItemType> {
+        registry().item_types()
+    }
+
+    pub fn id(self) -> &'static str {
+        &registry().item(self).id
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
-pub enum NPCType {
-    Fish,
-    Troll,
-    Spider,
+    pub fn from_id(id: &str) -> Option<ItemType> {
+        registry().find_item(id)
+    }
+
+    pub fn like(self) -> ItemType {
+        registry().item(self).like
+    }
+
+    // Whether holding this item counts as holding `item`: a mod's golden sword
+    // is a sword, but a sword is not a golden sword.
+    pub fn is(self, item: ItemType) -> bool {
+        self == item || self.like() == item
+    }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NPCType(pub(super) u16);
+
 impl NPCType {
+    pub const FISH: NPCType = NPCType(0);
+    pub const TROLL: NPCType = NPCType(1);
+    pub const SPIDER: NPCType = NPCType(2);
+
+    pub fn all() -> impl Iterator<Item = NPCType> {
+        registry().npc_types()
+    }
+
+    pub fn symbol(self) -> &'static str {
+        &registry().npc(self).glyph
+    }
+
+    // Percentage of the normal speed
+    pub fn speed(self) -> u32 {
+        registry().npc(self).speed
+    }
+
+    // Lowercase identifier used in map files.
+    pub fn id(self) -> &'static str {
+        &registry().npc(self).id
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn from_id(id: &str) -> Option<NPCType> {
+        registry().find_npc(id)
+    }
+
+    // The built-in NPC type this one behaves like
+    pub fn like(self) -> NPCType {
+        registry().npc(self).like
+    }
+
     // This function returns the TileType where the NPCType can move and spawn.
     pub fn allowed_tile(self) -> TileType {
-        match self {
-            NPCType::Fish => TileType::Water,
-            NPCType::Troll => TileType::Grass,
-            NPCType::Spider => TileType::Grass,
-        }
+        registry().npc(self).lives_on
+    }
+}
+
+// Items and NPCs are saved by id, like in map files
+impl Serialize for ItemType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ItemType, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        ItemType::from_id(&id).ok_or_else(|| D::Error::custom(format!("unknown item '{}'", id)))
+    }
+}
+
+impl Serialize for NPCType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for NPCType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<NPCType, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        NPCType::from_id(&id).ok_or_else(|| D::Error::custom(format!("unknown npc '{}'", id)))
     }
 }
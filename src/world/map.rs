@@ -0,0 +1,230 @@
+use crate::world::entities::{NPCType, NPC};
+use crate::world::terrain::{Terrain, TileType, TERRAIN_HEIGHT, TERRAIN_WIDTH};
+use crate::world::World;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const MAP_HEADER: &str = "# rust_ascii_rpg map v1";
+
+// A hand-made level: the terrain plus where the player and the NPCs start.
+//
+// Map files are plain text so they can also be tweaked by hand:
+//
+//   # rust_ascii_rpg map v1
+//   player 12 8
+//   npc troll 40 20
+//   tiles
+//   ^^^^^^^^...
+//   ^.....TT...
+//
+// followed by TERRAIN_HEIGHT rows of TERRAIN_WIDTH tile characters (see `TileType::map_char`).
+#[derive(Clone)]
+pub struct Map {
+    pub terrain: Terrain,
+    pub player_start: (i32, i32),
+    pub npcs: Vec<NPC>,
+}
+
+impl Map {
+    pub fn from_world(world: &World) -> Map {
+        Map {
+            terrain: world.terrain.clone(),
+            player_start: (world.player.x, world.player.y),
+            npcs: world.npcs.clone(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Map, io::Error> {
+        let content = fs::read_to_string(path)?;
+        Map::parse(&content).map_err(|message| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), message),
+            )
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        fs::write(path, self.serialize())
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut content = format!("{}\n", MAP_HEADER);
+        content.push_str(&format!(
+            "player {} {}\n",
+            self.player_start.0, self.player_start.1
+        ));
+        for npc in &self.npcs {
+            content.push_str(&format!("npc {} {} {}\n", npc.npc_type.id(), npc.x, npc.y));
+        }
+        content.push_str("tiles\n");
+        for row in self.terrain.tiles.chunks(TERRAIN_WIDTH) {
+            content.extend(row.iter().map(|tile| tile.map_char()));
+            content.push('\n');
+        }
+        content
+    }
+
+    pub fn parse(content: &str) -> Result<Map, String> {
+        let mut lines = content.lines().enumerate();
+        let mut player_start = None;
+        let mut npcs = Vec::new();
+
+        // header section: player start and npc spawns, until the "tiles" marker
+        loop {
+            let Some((line_number, line)) = lines.next() else {
+                return Err("missing 'tiles' section".to_string());
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["tiles"] => break,
+                ["player", x, y] => {
+                    player_start = Some(parse_position(x, y, line_number)?);
+                }
+                ["npc", id, x, y] => {
+                    let npc_type = NPCType::from_id(id).ok_or(format!(
+                        "line {}: unknown npc type '{}'",
+                        line_number + 1,
+                        id
+                    ))?;
+                    let (x, y) = parse_position(x, y, line_number)?;
+                    npcs.push(NPC {
+                        x,
+                        y,
+                        npc_type,
+                        energy: 0,
+                    });
+                }
+                _ => return Err(format!("line {}: cannot parse '{}'", line_number + 1, line)),
+            }
+        }
+
+        // tile grid
+        let mut terrain = Terrain {
+            tiles: [TileType::GRASS; TERRAIN_WIDTH * TERRAIN_HEIGHT],
+        };
+        for y in 0..TERRAIN_HEIGHT {
+            let Some((line_number, line)) = lines.next() else {
+                return Err(format!(
+                    "expected {} rows of tiles, found {}",
+                    TERRAIN_HEIGHT, y
+                ));
+            };
+            let row: Vec<char> = line.trim_end().chars().collect();
+            if row.len() != TERRAIN_WIDTH {
+                return Err(format!(
+                    "line {}: expected {} tiles, found {}",
+                    line_number + 1,
+                    TERRAIN_WIDTH,
+                    row.len()
+                ));
+            }
+            for (x, c) in row.into_iter().enumerate() {
+                terrain.tiles[y * TERRAIN_WIDTH + x] = TileType::from_map_char(c)
+                    .ok_or(format!("line {}: unknown tile '{}'", line_number + 1, c))?;
+            }
+        }
+
+        let map = Map {
+            terrain,
+            player_start: player_start.ok_or("missing player start")?,
+            npcs,
+        };
+        map.validate()?;
+        Ok(map)
+    }
+
+    // Check that everyone starts on a tile they are allowed to stand on.
+    pub fn validate(&self) -> Result<(), String> {
+        let (player_x, player_y) = self.player_start;
+        match self.tile_at(player_x, player_y).map(TileType::like) {
+            Some(TileType::GRASS) | Some(TileType::SAND) => {}
+            _ => {
+                return Err(format!(
+                    "player start ({}, {}) must be on Grass or Sand",
+                    player_x, player_y
+                ))
+            }
+        }
+
+        for (i, npc) in self.npcs.iter().enumerate() {
+            let allowed_tile = npc.npc_type.allowed_tile();
+            if self.tile_at(npc.x, npc.y) != Some(allowed_tile) {
+                return Err(format!(
+                    "{} spawn ({}, {}) must be on {}",
+                    npc.npc_type.id(),
+                    npc.x,
+                    npc.y,
+                    allowed_tile.name()
+                ));
+            }
+            if (npc.x, npc.y) == self.player_start
+                || self.npcs[..i]
+                    .iter()
+                    .any(|other| (other.x, other.y) == (npc.x, npc.y))
+            {
+                return Err(format!("two spawns share the tile ({}, {})", npc.x, npc.y));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn tile_at(&self, x: i32, y: i32) -> Option<TileType> {
+        if x < 0 || y < 0 || x >= TERRAIN_WIDTH as i32 || y >= TERRAIN_HEIGHT as i32 {
+            return None;
+        }
+        Some(self.terrain.tiles[y as usize * TERRAIN_WIDTH + x as usize])
+    }
+}
+
+fn parse_position(x: &str, y: &str, line_number: usize) -> Result<(i32, i32), String> {
+    match (x.parse(), y.parse()) {
+        (Ok(x), Ok(y)) => Ok((x, y)),
+        _ => Err(format!("line {}: invalid position", line_number + 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Balance;
+
+    #[test]
+    fn maps_survive_a_round_trip() {
+        let map = Map::from_world(&World::with_seed(8, Balance::default()));
+        let parsed = Map::parse(&map.serialize()).unwrap();
+        assert!(parsed.terrain.tiles == map.terrain.tiles);
+        assert_eq!(parsed.player_start, map.player_start);
+        let spawns = |map: &Map| -> Vec<(&str, i32, i32)> {
+            map.npcs
+                .iter()
+                .map(|npc| (npc.npc_type.id(), npc.x, npc.y))
+                .collect()
+        };
+        assert_eq!(spawns(&parsed), spawns(&map));
+        assert_eq!(parsed.serialize(), map.serialize());
+    }
+
+    #[test]
+    fn broken_maps_are_rejected() {
+        let map = Map::from_world(&World::with_seed(8, Balance::default())).serialize();
+        let without_row = map.trim_end().rsplit_once('\n').unwrap().0;
+        assert!(Map::parse(without_row)
+            .err()
+            .unwrap()
+            .contains("rows of tiles"));
+        let unknown_npc = map.replacen("tiles\n", "npc dragon 3 3\ntiles\n", 1);
+        assert!(Map::parse(&unknown_npc).err().unwrap().contains("dragon"));
+        let on_mountain = map.replacen("player", "player 0 0\n# was", 1);
+        assert!(Map::parse(&on_mountain)
+            .err()
+            .unwrap()
+            .contains("player start"));
+    }
+}
@@ -1,111 +1,310 @@
-use rand::Rng;
+use crate::events::GameEvent;
+use crate::tileset::Tileset;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use std::io;
+use std::path::Path;
+pub mod balance;
+pub mod clock;
 pub mod entities;
+pub mod map;
+pub mod registry;
+pub mod rules;
+pub mod save;
+pub mod stats;
 pub mod terrain;
+pub mod weather;
+pub use balance::{Balance, Difficulty};
+pub use clock::{Clock, DayPhase};
 pub use entities::*;
+pub use map::Map;
+pub use registry::Registry;
+pub use rules::Rules;
+pub use save::SaveGame;
+pub use stats::RunStats;
 pub use terrain::Terrain;
+pub use weather::Weather;
 
+#[derive(Clone)]
 pub struct World {
     pub terrain: Terrain,
     pub player: Player,
     pub npcs: Vec<NPC>,
+    // number of actions the player has taken
+    pub turn: u32,
+    pub weather: Weather,
+    pub balance: Balance,
+    pub stats: RunStats,
+    // the world is generated from this seed, and the game randomness comes from
+    // `rng` which is seeded with it, so a seed always plays out the same way.
+    // It is what StdRng wraps, named so its state can be saved.
+    pub seed: u64,
+    pub rng: ChaCha12Rng,
+    // events recorded since the game loop last collected them
+    pub events: Vec<GameEvent>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl World {
     pub fn new() -> World {
-        let new_terrain = Terrain::new();
+        World::with_seed(rand::random(), Balance::default())
+    }
+    pub fn with_seed(seed: u64, balance: Balance) -> World {
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let new_terrain = Terrain::new(&mut rng, &balance);
+        // the player and the creatures of the land spawn there, so none of
+        // them is cut off on an island where it cannot be met
+        let mainland = new_terrain.mainland();
 
         // Spawn player in grass
         let (player_x, player_y) =
-            World::find_spawn_location(&new_terrain, terrain::TileType::Grass);
+            World::find_spawn_location(&new_terrain, &mainland, terrain::TileType::GRASS, &mut rng);
 
         let mut world = Self {
             terrain: new_terrain,
             player: Player {
                 x: player_x,
                 y: player_y,
-                name: "alk".to_string(),
-                inventory: vec![ItemType::Sword],
+                name: String::new(), // Entered by the player when the game starts
+                inventory: vec![ItemType::SWORD],
                 dead: false,
             },
             npcs: Vec::new(),
+            turn: 0,
+            weather: Weather::Clear,
+            balance,
+            stats: RunStats::default(),
+            seed,
+            rng,
+            events: Vec::new(),
         };
 
         // spawn fishes
-        for _ in 0..3 {
-            world.spawn_npc(NPCType::Fish);
+        for _ in 0..balance.fish_count {
+            world.spawn_npc(NPCType::FISH, &mainland);
         }
 
         // spawn trolls
-        for _ in 0..5 {
-            world.spawn_npc(NPCType::Troll);
+        for _ in 0..balance.troll_count {
+            world.spawn_npc(NPCType::TROLL, &mainland);
         }
 
-        // spawn the spider
-        world.spawn_npc(NPCType::Spider);
+        // spawn spiders
+        for _ in 0..balance.spider_count {
+            world.spawn_npc(NPCType::SPIDER, &mainland);
+        }
 
-        return world;
-    }
-    fn find_spawn_location(terrain: &Terrain, tile_type: terrain::TileType) -> (i32, i32) {
-        let mut rng = rand::thread_rng();
+        // and the NPCs the mods add, if the world has tiles they can live on
+        for npc_type in NPCType::all() {
+            if !world.terrain.tiles.contains(&npc_type.allowed_tile()) {
+                continue;
+            }
+            for _ in 0..registry::registry().npc(npc_type).count {
+                world.spawn_npc(npc_type, &mainland);
+            }
+        }
 
+        // play with a generator seeded the way a loaded game's is, so a save of
+        // the start plays out exactly like the generated world
+        world.rng = ChaCha12Rng::seed_from_u64(seed);
+
+        world
+    }
+    // Build a world from a hand-made map instead of generating one. The map
+    // places the NPCs, the balance only tunes how the game plays.
+    pub fn from_map(map: Map, balance: Balance) -> World {
+        let (player_x, player_y) = map.player_start;
+        let seed = rand::random();
+        Self {
+            terrain: map.terrain,
+            player: Player {
+                x: player_x,
+                y: player_y,
+                name: String::new(),
+                inventory: vec![ItemType::SWORD],
+                dead: false,
+            },
+            npcs: map.npcs,
+            turn: 0,
+            weather: Weather::Clear,
+            balance,
+            stats: RunStats::default(),
+            seed,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            events: Vec::new(),
+        }
+    }
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        SaveGame::from_world(self).write(path)
+    }
+    pub fn load(path: &Path) -> Result<World, io::Error> {
+        Ok(SaveGame::read(path)?.into_world())
+    }
+    // Whether something living on `tile_type` may spawn on the tile: the ones
+    // of the land only on the mainland
+    fn spawns_on(
+        terrain: &Terrain,
+        mainland: &[bool],
+        tile_type: terrain::TileType,
+        index: usize,
+    ) -> bool {
+        terrain.tiles[index] == tile_type && (mainland[index] || !terrain::on_foot(tile_type))
+    }
+    fn find_spawn_location<R: Rng>(
+        terrain: &Terrain,
+        mainland: &[bool],
+        tile_type: terrain::TileType,
+        rng: &mut R,
+    ) -> (i32, i32) {
         loop {
             let x = rng.gen_range(0..terrain::TERRAIN_WIDTH);
             let y = rng.gen_range(0..terrain::TERRAIN_HEIGHT);
             let index = y * terrain::TERRAIN_WIDTH + x;
 
-            if terrain.tiles[index] == tile_type {
+            if World::spawns_on(terrain, mainland, tile_type, index) {
                 return (x as i32, y as i32);
             }
         }
     }
-    fn spawn_npc(&mut self, npc_type: NPCType) {
-        // find spawn location
-        let (x, y) = World::find_spawn_location(&self.terrain, npc_type.allowed_tile());
+    fn spawn_npc(&mut self, npc_type: NPCType, mainland: &[bool]) {
+        // find spawn location, away from the player and the other NPCs
+        let taken = |world: &World, x: i32, y: i32| {
+            (x, y) == (world.player.x, world.player.y)
+                || world.npcs.iter().any(|npc| npc.x == x && npc.y == y)
+        };
+        let allowed_tile = npc_type.allowed_tile();
+        let room = (0..self.terrain.tiles.len()).any(|index| {
+            let (x, y) = (
+                index % terrain::TERRAIN_WIDTH,
+                index / terrain::TERRAIN_WIDTH,
+            );
+            World::spawns_on(&self.terrain, mainland, allowed_tile, index)
+                && !taken(self, x as i32, y as i32)
+        });
+        if !room {
+            return;
+        }
+        let (x, y) = loop {
+            let (x, y) =
+                World::find_spawn_location(&self.terrain, mainland, allowed_tile, &mut self.rng);
+            if !taken(self, x, y) {
+                break (x, y);
+            }
+        };
         self.npcs.push(NPC {
-            x: x as i32,
-            y: y as i32,
-            npc_type: npc_type,
+            x,
+            y,
+            npc_type,
+            energy: 0,
         })
     }
-    pub fn draw_to_string(&self) -> String {
+    // Hand the recorded events over to the caller, usually to publish them on the EventBus.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+    pub fn move_player_to(&mut self, x: i32, y: i32) {
+        let from = (self.player.x, self.player.y);
+        self.player.x = x;
+        self.player.y = y;
+        self.events
+            .push(GameEvent::PlayerMoved { from, to: (x, y) });
+    }
+    pub fn give_item(&mut self, item: ItemType) {
+        self.player.inventory.push(item);
+        self.events.push(GameEvent::ItemAcquired(item));
+    }
+    pub fn kill_npc(&mut self, npc_index: usize) {
+        let npc = self.npcs.remove(npc_index);
+        self.events.push(GameEvent::NpcKilled {
+            npc_type: npc.npc_type,
+            x: npc.x,
+            y: npc.y,
+        });
+    }
+    pub fn kill_player(&mut self) {
+        self.player.dead = true;
+        self.events.push(GameEvent::PlayerDied);
+    }
+    pub fn set_tile(&mut self, x: i32, y: i32, tile_type: terrain::TileType) {
+        let index = y as usize * terrain::TERRAIN_WIDTH + x as usize;
+        let from = self.terrain.tiles[index];
+        if from != tile_type {
+            self.terrain.tiles[index] = tile_type;
+            self.events.push(GameEvent::TileChanged {
+                x,
+                y,
+                from,
+                to: tile_type,
+            });
+        }
+    }
+    pub fn clock(&self) -> Clock {
+        Clock::at_turn(self.turn)
+    }
+    // The night and the weather both limit how far the player sees
+    pub fn sight_radius(&self) -> Option<u32> {
+        match (
+            self.clock().phase().sight_radius(),
+            self.weather.sight_radius(),
+        ) {
+            (Some(night), Some(weather)) => Some(night.min(weather)),
+            (night, weather) => night.or(weather),
+        }
+    }
+    pub fn is_visible(&self, x: i32, y: i32) -> bool {
+        match self.sight_radius() {
+            Some(radius) => {
+                let dx = x - self.player.x;
+                let dy = y - self.player.y;
+                (dx * dx + dy * dy) as u32 <= radius * radius
+            }
+            None => true,
+        }
+    }
+    pub fn draw_to_string(&self, tileset: Tileset) -> String {
         let mut buffer = String::new();
+        let tint = self.clock().phase().tint();
 
         for y in 0..terrain::TERRAIN_HEIGHT {
+            if let Some(tint) = tint {
+                buffer.push_str(tint);
+            }
             for x in 0..terrain::TERRAIN_WIDTH {
-                if x == self.player.x as usize && y == self.player.y as usize {
-                    buffer.push_str("🏃");
+                if !self.is_visible(x as i32, y as i32) {
+                    buffer.push_str(tileset.hidden()); // Out of sight
+                } else if x == self.player.x as usize && y == self.player.y as usize {
+                    buffer.push_str(tileset.player());
                 } else {
                     let mut npc_drawn = false;
                     for npc in &self.npcs {
                         if x == npc.x as usize && y == npc.y as usize {
-                            let npc_symbol = match npc.npc_type {
-                                NPCType::Fish => "🐠",
-                                NPCType::Troll => "👹",
-                                NPCType::Spider => "🕷️ ",
-                            };
-                            buffer.push_str(npc_symbol);
+                            buffer.push_str(tileset.npc(npc.npc_type));
                             npc_drawn = true;
                             break; // Only one NPC can occupy a tile, no need to check others
                         }
                     }
 
                     if !npc_drawn {
-                        let index = y * terrain::TERRAIN_WIDTH + x;
-                        let symbol = match self.terrain.tiles[index] {
-                            terrain::TileType::Grass => "🟩",
-                            terrain::TileType::Tree => "🌲",
-                            terrain::TileType::Water => "💧",
-                            terrain::TileType::Mountain => "🗻",
-                            terrain::TileType::Sand => "🟨",
-                            terrain::TileType::Castle => "🏰",
-                            terrain::TileType::Chest => "📦",
-                            terrain::TileType::SpiderWeb => "🕸️ ",
-                            terrain::TileType::Heart => "❤️ ",
-                        };
-                        buffer.push_str(symbol);
+                        match tileset.weather(self.weather) {
+                            Some(overlay) if self.weather.covers(x, y, self.turn) => {
+                                buffer.push_str(overlay);
+                            }
+                            _ => {
+                                let index = y * terrain::TERRAIN_WIDTH + x;
+                                buffer.push_str(tileset.tile(self.terrain.tiles[index]));
+                            }
+                        }
                     }
                 }
             }
+            if tint.is_some() {
+                buffer.push_str("\x1b[0m");
+            }
             buffer.push_str("\r\n"); // Newline at the end of each row
         }
 
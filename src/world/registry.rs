@@ -0,0 +1,328 @@
+use crate::world::entities::{ItemType, NPCType};
+use crate::world::terrain::TileType;
+#[cfg(not(test))]
+use std::sync::RwLock;
+
+// The tile, NPC and item types of the game: the built-in ones, then the ones
+// the mods add. Types only hold what they look like, how they behave comes
+// from the built-in type they are `like`.
+#[derive(PartialEq)]
+pub struct TileDef {
+    pub id: String,
+    // the built-in tile it behaves like, itself for the built-in ones
+    pub like: TileType,
+    pub glyph: String,
+    pub ascii: String,
+    // stores the tile in map files and saves
+    pub map_char: char,
+}
+
+#[derive(PartialEq)]
+pub struct NpcDef {
+    pub id: String,
+    pub like: NPCType,
+    pub glyph: String,
+    pub ascii: String,
+    // percentage of the normal speed
+    pub speed: u32,
+    // the tile it moves and spawns on
+    pub lives_on: TileType,
+    // how many a generated world gets, the balance counts the built-in ones
+    pub count: usize,
+}
+
+#[derive(PartialEq)]
+pub struct ItemDef {
+    pub id: String,
+    pub like: ItemType,
+    pub glyph: String,
+    pub ascii: String,
+}
+
+#[derive(PartialEq)]
+pub struct Registry {
+    pub tiles: Vec<TileDef>,
+    pub npcs: Vec<NpcDef>,
+    pub items: Vec<ItemDef>,
+}
+
+#[cfg(not(test))]
+static REGISTRY: RwLock<Option<&'static Registry>> = RwLock::new(None);
+
+#[cfg(not(test))]
+fn current() -> Option<&'static Registry> {
+    *REGISTRY.read().unwrap()
+}
+
+#[cfg(not(test))]
+fn set(registry: &'static Registry) {
+    *REGISTRY.write().unwrap() = Some(registry);
+}
+
+// Every test runs on a thread of its own, which gets its own registry so the
+// tests installing mods do not change the types of the others
+#[cfg(test)]
+thread_local! {
+    static REGISTRY: std::cell::Cell<Option<&'static Registry>> = const { std::cell::Cell::new(None) };
+}
+
+#[cfg(test)]
+fn current() -> Option<&'static Registry> {
+    REGISTRY.with(|registry| registry.get())
+}
+
+#[cfg(test)]
+fn set(registry: &'static Registry) {
+    REGISTRY.with(|current| current.set(Some(registry)));
+}
+
+// The types in use, the built-in ones until mods are installed
+pub fn registry() -> &'static Registry {
+    current().unwrap_or_else(|| {
+        let registry = Box::leak(Box::new(Registry::built_in()));
+        set(registry);
+        registry
+    })
+}
+
+// Replace the types in use. The previous registry is leaked so the glyphs and
+// ids handed out stay valid, it only happens when the mods are (re)loaded and
+// change a type.
+pub fn install(registry: Registry) {
+    if self::registry() != &registry {
+        restore(Box::leak(Box::new(registry)));
+    }
+}
+
+// Go back to a registry that was in use before
+pub fn restore(registry: &'static Registry) {
+    set(registry);
+}
+
+// Puts the registry in use when it was made back in place when dropped, for
+// what installs one only for a while
+pub struct Restore(&'static Registry);
+
+impl Restore {
+    pub fn current() -> Restore {
+        Restore(registry())
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        restore(self.0);
+    }
+}
+
+fn tile(id: &str, like: TileType, glyph: &str, ascii: &str, map_char: char) -> TileDef {
+    TileDef {
+        id: id.to_string(),
+        like,
+        glyph: glyph.to_string(),
+        ascii: ascii.to_string(),
+        map_char,
+    }
+}
+
+fn npc(
+    id: &str,
+    like: NPCType,
+    glyph: &str,
+    ascii: &str,
+    speed: u32,
+    lives_on: TileType,
+) -> NpcDef {
+    NpcDef {
+        id: id.to_string(),
+        like,
+        glyph: glyph.to_string(),
+        ascii: ascii.to_string(),
+        speed,
+        lives_on,
+        count: 0,
+    }
+}
+
+fn item(id: &str, like: ItemType, glyph: &str, ascii: &str) -> ItemDef {
+    ItemDef {
+        id: id.to_string(),
+        like,
+        glyph: glyph.to_string(),
+        ascii: ascii.to_string(),
+    }
+}
+
+impl Registry {
+    // In the order of the constants of TileType, NPCType and ItemType
+    pub fn built_in() -> Registry {
+        Registry {
+            tiles: vec![
+                tile("grass", TileType::GRASS, "🟩", "\x1b[32m.\x1b[39m", '.'),
+                tile("tree", TileType::TREE, "🌲", "\x1b[32mT\x1b[39m", 'T'),
+                tile("water", TileType::WATER, "💧", "\x1b[34m~\x1b[39m", '~'),
+                tile(
+                    "mountain",
+                    TileType::MOUNTAIN,
+                    "🗻",
+                    "\x1b[37m^\x1b[39m",
+                    '^',
+                ),
+                tile("sand", TileType::SAND, "🟨", "\x1b[33m:\x1b[39m", ':'),
+                tile("castle", TileType::CASTLE, "🏰", "\x1b[35mC\x1b[39m", 'C'),
+                tile("chest", TileType::CHEST, "📦", "\x1b[33m$\x1b[39m", '$'),
+                tile(
+                    "spider-web",
+                    TileType::SPIDER_WEB,
+                    "🕸️ ",
+                    "\x1b[37m#\x1b[39m",
+                    '#',
+                ),
+                tile("heart", TileType::HEART, "❤️ ", "\x1b[31mH\x1b[39m", 'H'),
+                tile(
+                    "shallow-water",
+                    TileType::SHALLOW_WATER,
+                    "🟦",
+                    "\x1b[36m~\x1b[39m",
+                    'w',
+                ),
+            ],
+            npcs: vec![
+                npc(
+                    "fish",
+                    NPCType::FISH,
+                    "🐠",
+                    "\x1b[96mf\x1b[39m",
+                    150,
+                    TileType::WATER,
+                ),
+                npc(
+                    "troll",
+                    NPCType::TROLL,
+                    "👹",
+                    "\x1b[91mt\x1b[39m",
+                    50,
+                    TileType::GRASS,
+                ),
+                npc(
+                    "spider",
+                    NPCType::SPIDER,
+                    "🕷️ ",
+                    "\x1b[95ms\x1b[39m",
+                    100,
+                    TileType::GRASS,
+                ),
+            ],
+            items: vec![
+                item("sword", ItemType::SWORD, "🗡️ ", "/"),
+                item("axe", ItemType::AXE, "🪓", "P"),
+                item("harpoon", ItemType::HARPOON, "🔱", "Y"),
+                item("snorkel", ItemType::SNORKEL, "🤿", "o"),
+                item("key", ItemType::KEY, "🗝️ ", "k"),
+            ],
+        }
+    }
+
+    pub fn tile(&self, tile_type: TileType) -> &TileDef {
+        &self.tiles[tile_type.0 as usize]
+    }
+    pub fn npc(&self, npc_type: NPCType) -> &NpcDef {
+        &self.npcs[npc_type.0 as usize]
+    }
+    pub fn item(&self, item: ItemType) -> &ItemDef {
+        &self.items[item.0 as usize]
+    }
+    pub fn find_tile(&self, id: &str) -> Option<TileType> {
+        let index = self.tiles.iter().position(|def| def.id == id)?;
+        Some(TileType(index as u16))
+    }
+    pub fn find_npc(&self, id: &str) -> Option<NPCType> {
+        let index = self.npcs.iter().position(|def| def.id == id)?;
+        Some(NPCType(index as u16))
+    }
+    pub fn find_item(&self, id: &str) -> Option<ItemType> {
+        let index = self.items.iter().position(|def| def.id == id)?;
+        Some(ItemType(index as u16))
+    }
+    pub fn tile_types(&self) -> impl Iterator<Item = TileType> {
+        (0..self.tiles.len() as u16).map(TileType)
+    }
+    pub fn npc_types(&self) -> impl Iterator<Item = NPCType> {
+        (0..self.npcs.len() as u16).map(NPCType)
+    }
+    pub fn item_types(&self) -> impl Iterator<Item = ItemType> {
+        (0..self.items.len() as u16).map(ItemType)
+    }
+
+    // Whether every type of `previous` is still there under the same index, so
+    // the worlds and rules using them stay valid when the mods are reloaded
+    pub fn keeps(&self, previous: &Registry) -> Result<(), String> {
+        kept(
+            "tile",
+            self.tiles.iter().map(|def| def.id.as_str()),
+            previous.tiles.iter().map(|def| def.id.as_str()),
+        )?;
+        kept(
+            "npc",
+            self.npcs.iter().map(|def| def.id.as_str()),
+            previous.npcs.iter().map(|def| def.id.as_str()),
+        )?;
+        kept(
+            "item",
+            self.items.iter().map(|def| def.id.as_str()),
+            previous.items.iter().map(|def| def.id.as_str()),
+        )
+    }
+
+    // Add the tile, or replace the one with the same id in place so maps and
+    // saves using it still load. Returns whether one was replaced.
+    pub fn add_tile(&mut self, def: TileDef) -> Result<bool, String> {
+        if let Some(other) = self
+            .tiles
+            .iter()
+            .find(|other| other.map_char == def.map_char && other.id != def.id)
+        {
+            return Err(format!(
+                "tile '{}': map character '{}' is already used by '{}'",
+                def.id, def.map_char, other.id
+            ));
+        }
+        Ok(replace_or_push(&mut self.tiles, def, |def| &def.id))
+    }
+    pub fn add_npc(&mut self, def: NpcDef) -> bool {
+        replace_or_push(&mut self.npcs, def, |def| &def.id)
+    }
+    pub fn add_item(&mut self, def: ItemDef) -> bool {
+        replace_or_push(&mut self.items, def, |def| &def.id)
+    }
+}
+
+fn replace_or_push<T>(defs: &mut Vec<T>, def: T, id: impl Fn(&T) -> &String) -> bool {
+    match defs.iter().position(|other| id(other) == id(&def)) {
+        Some(index) => {
+            defs[index] = def;
+            true
+        }
+        None => {
+            defs.push(def);
+            false
+        }
+    }
+}
+
+fn kept<'a>(
+    kind: &str,
+    ids: impl Iterator<Item = &'a str>,
+    previous: impl Iterator<Item = &'a str>,
+) -> Result<(), String> {
+    let ids: Vec<&str> = ids.collect();
+    for (index, id) in previous.enumerate() {
+        if ids.get(index) != Some(&id) {
+            return Err(format!(
+                "{} '{}' is gone, restart the game to remove types",
+                kind, id
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,550 @@
+use crate::events::GameEvent;
+use crate::message_log::Category;
+use crate::outcome::{Interaction, Outcome};
+use crate::scheduler::ACTION_COST;
+use crate::world::balance::Balance;
+use crate::world::entities::ItemType;
+use crate::world::stats::Stat;
+use crate::world::terrain::{TileType, TERRAIN_WIDTH};
+use crate::world::World;
+use rand::Rng;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// Default location of the extra interaction rules, loaded when present
+pub const RULES_FILE: &str = "rules.toml";
+// The interactions of the game, the fields are described in the file
+const BUILT_IN_RULES: &str = include_str!("rules.toml");
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Chance {
+    Percent(u32),
+    // one of the chances of the balance config, e.g. "chest_chance"
+    Balance(String),
+}
+
+impl Chance {
+    fn percent(&self, balance: &Balance) -> u32 {
+        match self {
+            Chance::Percent(percent) => *percent,
+            Chance::Balance(name) => balance.chance(name).unwrap_or(0),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub tile: TileType,
+    #[serde(default)]
+    pub requires: Vec<ItemType>,
+    #[serde(default)]
+    pub lacks: Vec<ItemType>,
+    pub chance: Option<Chance>,
+    #[serde(default)]
+    pub unique: bool,
+    #[serde(default)]
+    pub consumes: Vec<ItemType>,
+    pub becomes: Option<TileType>,
+    #[serde(default)]
+    pub enter: bool,
+    pub loot: Option<ItemType>,
+    #[serde(default)]
+    pub kills: bool,
+    #[serde(default)]
+    pub wins: bool,
+    pub message: String,
+    #[serde(default)]
+    pub category: Category,
+    #[serde(default = "default_cost")]
+    pub cost: u32,
+    pub counts: Option<Stat>,
+}
+
+fn default_cost() -> u32 {
+    ACTION_COST
+}
+
+impl Rule {
+    fn validate(&self) -> Result<(), String> {
+        match &self.chance {
+            Some(Chance::Percent(percent)) if *percent > 100 => {
+                return Err("chance must be between 0 and 100".to_string())
+            }
+            Some(Chance::Balance(name)) if Balance::default().chance(name).is_none() => {
+                return Err(format!("unknown chance '{}'", name))
+            }
+            _ => {}
+        }
+        if let Some(item) = self
+            .consumes
+            .iter()
+            .find(|item| !self.requires.contains(item))
+        {
+            return Err(format!("consumes {} without requiring it", item.id()));
+        }
+        if self.unique && self.becomes.is_none() {
+            return Err("unique needs the tile to become something".to_string());
+        }
+        if self.message.is_empty() {
+            return Err("missing message".to_string());
+        }
+        if self.cost == 0 {
+            return Err("cost must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    // Whether the rule applies, rolling its chance if it has one
+    fn matches(&self, world: &mut World, tile: TileType) -> bool {
+        let player = &world.player;
+        if self.tile != tile
+            || !self.requires.iter().all(|&item| player.has(item))
+            || self.lacks.iter().any(|&item| player.has(item))
+        {
+            return false;
+        }
+        if let Some(chance) = &self.chance {
+            if world.rng.gen_range(0..100) >= chance.percent(&world.balance) {
+                return false;
+            }
+        }
+        !self.unique
+            || self
+                .becomes
+                .is_some_and(|becomes| !world.terrain.tiles.contains(&becomes))
+    }
+
+    fn apply(&self, world: &mut World, x: i32, y: i32) -> Outcome {
+        for item in &self.consumes {
+            let inventory = &mut world.player.inventory;
+            if let Some(index) = inventory.iter().position(|other| other.is(*item)) {
+                inventory.remove(index);
+            }
+        }
+        if let Some(becomes) = self.becomes {
+            world.set_tile(x, y, becomes);
+        }
+        if self.enter {
+            world.move_player_to(x, y);
+        }
+        if let Some(loot) = self.loot {
+            world.give_item(loot);
+        }
+        if self.kills {
+            world.kill_player();
+        }
+        if self.wins {
+            world.events.push(GameEvent::GameWon);
+        }
+        Outcome::Interaction(Interaction {
+            message: self.message.clone(),
+            category: self.category,
+            item: self.loot.or(self.requires.first().copied()),
+            cost: self.cost,
+            counts: self.counts,
+        })
+    }
+}
+
+// rules.toml, a list of [[rule]] tables
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    rule: Vec<Rule>,
+}
+
+// What happens when the player moves onto a tile, checked in order
+pub struct Rules {
+    rules: Vec<Rule>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::parse(BUILT_IN_RULES).expect("the built-in rules are valid")
+    }
+}
+
+impl Rules {
+    pub fn empty() -> Rules {
+        Rules { rules: Vec::new() }
+    }
+
+    // Load the extra rules, checked before the ones of the mods and the
+    // built-in ones, or only those when there is no such file.
+    pub fn load(path: &Path, mods: Rules) -> Result<Rules, String> {
+        let mut rules = mods;
+        rules.append(Rules::default());
+        if !path.exists() {
+            return Ok(rules);
+        }
+        let content = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let extra =
+            Rules::parse(&content).map_err(|message| format!("{}: {}", path.display(), message))?;
+        rules.rules.splice(0..0, extra.rules);
+        Ok(rules)
+    }
+
+    pub fn parse(content: &str) -> Result<Rules, String> {
+        let file: RulesFile = toml::from_str(content).map_err(|error| error.to_string())?;
+        for (index, rule) in file.rule.iter().enumerate() {
+            rule.validate()
+                .map_err(|message| format!("rule {}: {}", index + 1, message))?;
+        }
+        Ok(Rules { rules: file.rule })
+    }
+
+    // Check these rules after the ones already there
+    pub fn append(&mut self, other: Rules) {
+        self.rules.extend(other.rules);
+    }
+
+    // Apply the first rule matching the tile at (x, y), if any. The tiles of
+    // mods without a matching rule of their own get the rules of the built-in
+    // tile they are like.
+    pub fn apply(&self, world: &mut World, x: i32, y: i32) -> Option<Outcome> {
+        let tile = world.terrain.tiles[y as usize * TERRAIN_WIDTH + x as usize];
+        let rule = match self.rules.iter().find(|rule| rule.matches(world, tile)) {
+            Some(rule) => rule,
+            None if tile.like() != tile => {
+                let like = tile.like();
+                self.rules.iter().find(|rule| rule.matches(world, like))?
+            }
+            None => return None,
+        };
+        Some(rule.apply(world, x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Case {
+        name: &'static str,
+        tile: TileType,
+        inventory: &'static [ItemType],
+        chest_chance: u32,
+        message: &'static str,
+        tile_after: TileType,
+        entered: bool,
+        inventory_after: &'static [ItemType],
+        dead: bool,
+        won: bool,
+    }
+
+    const CASES: &[Case] = &[
+        Case {
+            name: "walk on grass",
+            tile: TileType::GRASS,
+            inventory: &[],
+            chest_chance: 15,
+            message: "wandering",
+            tile_after: TileType::GRASS,
+            entered: true,
+            inventory_after: &[],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "swim with the snorkel",
+            tile: TileType::WATER,
+            inventory: &[ItemType::SNORKEL],
+            chest_chance: 15,
+            message: "swimming",
+            tile_after: TileType::WATER,
+            entered: true,
+            inventory_after: &[ItemType::SNORKEL],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "water without the snorkel",
+            tile: TileType::WATER,
+            inventory: &[ItemType::SWORD],
+            chest_chance: 15,
+            message: "water-blocked",
+            tile_after: TileType::WATER,
+            entered: false,
+            inventory_after: &[ItemType::SWORD],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "climb a mountain",
+            tile: TileType::MOUNTAIN,
+            inventory: &[ItemType::SWORD],
+            chest_chance: 15,
+            message: "mountain-blocked",
+            tile_after: TileType::MOUNTAIN,
+            entered: false,
+            inventory_after: &[ItemType::SWORD],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "chop a tree",
+            tile: TileType::TREE,
+            inventory: &[ItemType::AXE],
+            chest_chance: 0,
+            message: "tree-chopped",
+            tile_after: TileType::GRASS,
+            entered: false,
+            inventory_after: &[ItemType::AXE],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "uncover the chest",
+            tile: TileType::TREE,
+            inventory: &[ItemType::AXE],
+            chest_chance: 100,
+            message: "chest-uncovered",
+            tile_after: TileType::CHEST,
+            entered: false,
+            inventory_after: &[ItemType::AXE],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "tree without the axe",
+            tile: TileType::TREE,
+            inventory: &[ItemType::SWORD],
+            chest_chance: 100,
+            message: "tree-blocked",
+            tile_after: TileType::TREE,
+            entered: false,
+            inventory_after: &[ItemType::SWORD],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "loot the chest",
+            tile: TileType::CHEST,
+            inventory: &[ItemType::AXE],
+            chest_chance: 15,
+            message: "chest-looted",
+            tile_after: TileType::CHEST,
+            entered: false,
+            inventory_after: &[ItemType::AXE, ItemType::HARPOON],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "empty chest",
+            tile: TileType::CHEST,
+            inventory: &[ItemType::HARPOON],
+            chest_chance: 15,
+            message: "chest-empty",
+            tile_after: TileType::CHEST,
+            entered: false,
+            inventory_after: &[ItemType::HARPOON],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "open the castle",
+            tile: TileType::CASTLE,
+            inventory: &[ItemType::KEY],
+            chest_chance: 15,
+            message: "castle-opened",
+            tile_after: TileType::HEART,
+            entered: false,
+            inventory_after: &[ItemType::KEY],
+            dead: false,
+            won: true,
+        },
+        Case {
+            name: "locked castle",
+            tile: TileType::CASTLE,
+            inventory: &[ItemType::HARPOON],
+            chest_chance: 15,
+            message: "castle-locked",
+            tile_after: TileType::CASTLE,
+            entered: false,
+            inventory_after: &[ItemType::HARPOON],
+            dead: false,
+            won: false,
+        },
+        Case {
+            name: "spider web",
+            tile: TileType::SPIDER_WEB,
+            inventory: &[ItemType::SWORD],
+            chest_chance: 15,
+            message: "trapped-in-web",
+            tile_after: TileType::SPIDER_WEB,
+            entered: false,
+            inventory_after: &[ItemType::SWORD],
+            dead: true,
+            won: false,
+        },
+    ];
+
+    // A world with the player next to the tile to move onto, and no chest
+    fn world_with(tile: TileType, inventory: &[ItemType], chest_chance: u32) -> World {
+        let mut world = World::with_seed(0, Balance::default());
+        for other in world.terrain.tiles.iter_mut() {
+            if *other == TileType::CHEST {
+                *other = TileType::GRASS;
+            }
+        }
+        world.npcs.clear();
+        (world.player.x, world.player.y) = (10, 10);
+        world.terrain.tiles[10 * TERRAIN_WIDTH + 11] = tile;
+        world.player.inventory = inventory.to_vec();
+        world.balance.chest_chance = chest_chance;
+        world
+    }
+
+    #[test]
+    fn built_in_rules() {
+        let rules = Rules::default();
+        for case in CASES {
+            let mut world = world_with(case.tile, case.inventory, case.chest_chance);
+            let outcome = rules.apply(&mut world, 11, 10);
+            let Some(Outcome::Interaction(interaction)) = outcome else {
+                panic!("{}: no rule applied", case.name);
+            };
+            assert_eq!(interaction.message, case.message, "{}", case.name);
+            assert!(
+                world.terrain.tiles[10 * TERRAIN_WIDTH + 11] == case.tile_after,
+                "{}: tile after",
+                case.name
+            );
+            assert_eq!(
+                (world.player.x, world.player.y) == (11, 10),
+                case.entered,
+                "{}: entered",
+                case.name
+            );
+            assert!(
+                world.player.inventory == case.inventory_after,
+                "{}: inventory after",
+                case.name
+            );
+            assert_eq!(world.player.dead, case.dead, "{}: dead", case.name);
+            let won = world
+                .events
+                .iter()
+                .any(|event| matches!(event, GameEvent::GameWon));
+            assert_eq!(won, case.won, "{}: won", case.name);
+        }
+    }
+
+    #[test]
+    fn rules_count_for_their_stats() {
+        // a rule of a mod telling the swimming message while walking on grass
+        let mut rules =
+            Rules::parse("[[rule]]\ntile = \"grass\"\nenter = true\nmessage = \"swimming\"")
+                .unwrap();
+        rules.append(Rules::default());
+        // tiles swum, trees chopped and deaths by web once moved onto the tile
+        let cases = [
+            (TileType::GRASS, None, 15, (0, 0, 0)),
+            (TileType::WATER, Some(ItemType::SNORKEL), 15, (1, 0, 0)),
+            (TileType::TREE, Some(ItemType::AXE), 0, (0, 1, 0)),
+            (TileType::TREE, Some(ItemType::AXE), 100, (0, 1, 0)),
+            (TileType::SPIDER_WEB, None, 15, (0, 0, 1)),
+        ];
+        for (tile, item, chest_chance, expected) in cases {
+            let mut world = world_with(tile, item.as_slice(), chest_chance);
+            let outcome = rules.apply(&mut world, 11, 10).unwrap();
+            world.stats.record_outcome(&outcome);
+            let stats = world.stats;
+            assert_eq!(
+                (stats.tiles_swum, stats.trees_chopped, stats.deaths_by_web),
+                expected,
+                "{}",
+                tile.id()
+            );
+        }
+    }
+
+    #[test]
+    fn only_one_chest() {
+        let rules = Rules::default();
+        let mut world = world_with(TileType::TREE, &[ItemType::AXE], 100);
+        world.terrain.tiles[0] = TileType::CHEST;
+        let outcome = rules.apply(&mut world, 11, 10);
+        assert!(matches!(
+            outcome,
+            Some(Outcome::Interaction(interaction)) if interaction.message == "tree-chopped"
+        ));
+    }
+
+    #[test]
+    fn no_rule_for_hearts() {
+        let mut world = world_with(TileType::HEART, &[ItemType::SWORD], 15);
+        assert!(Rules::default().apply(&mut world, 11, 10).is_none());
+    }
+
+    #[test]
+    fn extra_rules_come_first() {
+        let extra = r#"
+            [[rule]]
+            tile = "castle"
+            requires = ["key", "axe"]
+            consumes = ["axe"]
+            loot = "snorkel"
+            message = "castle-trade"
+            cost = 300
+        "#;
+        let mut rules = Rules::default();
+        rules.rules.splice(0..0, Rules::parse(extra).unwrap().rules);
+
+        let mut world = world_with(TileType::CASTLE, &[ItemType::KEY, ItemType::AXE], 15);
+        let outcome = rules.apply(&mut world, 11, 10);
+        assert!(
+            outcome
+                == Some(Outcome::Interaction(Interaction {
+                    message: "castle-trade".to_string(),
+                    category: Category::Info,
+                    item: Some(ItemType::SNORKEL),
+                    cost: 300,
+                    counts: None,
+                }))
+        );
+        assert!(world.player.inventory == [ItemType::KEY, ItemType::SNORKEL]);
+
+        // without the axe, the built-in rule still opens the castle
+        let mut world = world_with(TileType::CASTLE, &[ItemType::KEY], 15);
+        let outcome = rules.apply(&mut world, 11, 10).unwrap();
+        assert_eq!(outcome.message_id(), "castle-opened");
+    }
+
+    #[test]
+    fn invalid_rules() {
+        for (content, error) in [
+            (
+                "[[rule]]\ntile = \"lava\"\nmessage = \"hot\"",
+                "unknown tile 'lava'",
+            ),
+            (
+                "[[rule]]\ntile = \"tree\"\nchance = 150\nmessage = \"x\"",
+                "rule 1: chance must be between 0 and 100",
+            ),
+            (
+                "[[rule]]\ntile = \"tree\"\nchance = \"luck\"\nmessage = \"x\"",
+                "rule 1: unknown chance 'luck'",
+            ),
+            (
+                "[[rule]]\ntile = \"tree\"\nconsumes = [\"axe\"]\nmessage = \"x\"",
+                "rule 1: consumes axe without requiring it",
+            ),
+            (
+                "[[rule]]\ntile = \"tree\"\nunique = true\nmessage = \"x\"",
+                "rule 1: unique needs the tile to become something",
+            ),
+            (
+                "[[rule]]\ntile = \"tree\"\nmessage = \"x\"\nteleport = true",
+                "unknown field `teleport`",
+            ),
+        ] {
+            let message = Rules::parse(content).err().unwrap();
+            assert!(message.contains(error), "{}: {}", error, message);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::world::balance::Balance;
+use crate::world::entities::{Player, NPC};
+use crate::world::stats::RunStats;
+use crate::world::terrain::Terrain;
+use crate::world::weather::Weather;
+use crate::world::World;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Default location of the saved game
+pub const SAVE_FILE: &str = "savegame.json";
+// Bumped whenever the save format changes in an incompatible way
+const SAVE_VERSION: u32 = 1;
+
+// Everything needed to pick a game up where it was left
+#[derive(Serialize, Deserialize)]
+pub struct SaveGame {
+    version: u32,
+    seed: u64,
+    turn: u32,
+    weather: Weather,
+    // saves from before the balance config play with the default one
+    #[serde(default)]
+    balance: Balance,
+    #[serde(default)]
+    stats: RunStats,
+    terrain: Terrain,
+    player: Player,
+    npcs: Vec<NPC>,
+    // the state of the generator, so the game goes on exactly as it would have
+    #[serde(default)]
+    rng: Option<ChaCha12Rng>,
+}
+
+impl SaveGame {
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn from_world(world: &World) -> SaveGame {
+        SaveGame {
+            version: SAVE_VERSION,
+            seed: world.seed,
+            turn: world.turn,
+            weather: world.weather,
+            balance: world.balance,
+            stats: world.stats,
+            terrain: world.terrain.clone(),
+            player: world.player.clone(),
+            npcs: world.npcs.clone(),
+            rng: Some(world.rng.clone()),
+        }
+    }
+
+    pub fn into_world(self) -> World {
+        World {
+            terrain: self.terrain,
+            player: self.player,
+            npcs: self.npcs,
+            turn: self.turn,
+            weather: self.weather,
+            balance: self.balance,
+            stats: self.stats,
+            seed: self.seed,
+            // saves from before the generator state was kept derive one from the seed
+            // and the turn, so they still play out the same way every time
+            rng: self.rng.unwrap_or_else(|| {
+                ChaCha12Rng::seed_from_u64(self.seed ^ u64::from(self.turn).rotate_left(32))
+            }),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), io::Error> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(path, content)
+    }
+
+    pub fn read(path: &Path) -> Result<SaveGame, io::Error> {
+        let content = fs::read_to_string(path)?;
+        let save: SaveGame = serde_json::from_str(&content)
+            .map_err(|error| error.to_string())
+            .and_then(|save: SaveGame| save.validate().map(|()| save))
+            .map_err(|message| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), message),
+                )
+            })?;
+        Ok(save)
+    }
+
+    // Whether this version of the game can play the save
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != SAVE_VERSION {
+            return Err(format!("unsupported save version {}", self.version));
+        }
+        self.balance.validate()
+    }
+}
@@ -0,0 +1,62 @@
+use crate::events::GameEvent;
+use crate::outcome::Outcome;
+use crate::world::entities::NPCType;
+use serde::{Deserialize, Serialize};
+
+// What an interaction rule counts for, see the `counts` field of the rules
+#[derive(Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stat {
+    TilesSwum,
+    TreesChopped,
+    DeathsByWeb,
+}
+
+// What the player did in the current game, saved along with the world
+#[derive(Copy, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunStats {
+    pub steps: u32,
+    pub tiles_swum: u32,
+    pub trees_chopped: u32,
+    pub trolls_slain: u32,
+    pub spiders_slain: u32,
+    pub fish_caught: u32,
+    pub deaths_by_web: u32,
+    // turn the castle was opened on
+    pub won_at: Option<u32>,
+}
+
+impl RunStats {
+    pub fn kills(&self) -> u32 {
+        self.trolls_slain + self.spiders_slain + self.fish_caught
+    }
+
+    // Interactions count for the stat their rule names
+    pub fn record_outcome(&mut self, outcome: &Outcome) {
+        let Outcome::Interaction(interaction) = outcome else {
+            return;
+        };
+        match interaction.counts {
+            Some(Stat::TilesSwum) => self.tiles_swum += 1,
+            Some(Stat::TreesChopped) => self.trees_chopped += 1,
+            Some(Stat::DeathsByWeb) => self.deaths_by_web += 1,
+            None => {}
+        }
+    }
+
+    pub fn record_event(&mut self, event: &GameEvent, turn: u32) {
+        match event {
+            GameEvent::PlayerMoved { .. } => self.steps += 1,
+            // the NPCs of mods count as the built-in ones they are like
+            GameEvent::NpcKilled { npc_type, .. } => match npc_type.like() {
+                NPCType::TROLL => self.trolls_slain += 1,
+                NPCType::SPIDER => self.spiders_slain += 1,
+                NPCType::FISH => self.fish_caught += 1,
+                _ => {}
+            },
+            GameEvent::GameWon => self.won_at = Some(turn),
+            GameEvent::ItemAcquired(_) | GameEvent::TileChanged { .. } | GameEvent::PlayerDied => {}
+        }
+    }
+}
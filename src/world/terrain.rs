@@ -1,58 +1,165 @@
+use crate::world::balance::Balance;
+use crate::world::registry::registry;
 use rand::Rng;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 pub const TERRAIN_WIDTH: usize = 100;
 pub const TERRAIN_HEIGHT: usize = 50;
-const FOREST_RADIUS: usize = 10;
-const POND_RADIUS: usize = 15;
-
-#[derive(Copy, Clone, PartialEq, Eq)]
-pub enum TileType {
-    Grass,
-    Tree,
-    Water,
-    Mountain,
-    Sand,
-    Castle,
-    Chest,
-    SpiderWeb,
-    Heart,
+
+// A tile type of the registry, see registry::TileDef. The built-in ones are
+// constants, the ones added by mods are looked up by id.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TileType(pub(super) u16);
+
+impl TileType {
+    pub const GRASS: TileType = TileType(0);
+    pub const TREE: TileType = TileType(1);
+    pub const WATER: TileType = TileType(2);
+    pub const MOUNTAIN: TileType = TileType(3);
+    pub const SAND: TileType = TileType(4);
+    pub const CASTLE: TileType = TileType(5);
+    pub const CHEST: TileType = TileType(6);
+    pub const SPIDER_WEB: TileType = TileType(7);
+    pub const HEART: TileType = TileType(8);
+    // sand flooded by the rain, walkable without a snorkel
+    pub const SHALLOW_WATER: TileType = TileType(9);
+
+    // Every tile type, in the order the map editor palette shows them.
+    pub fn all() -> impl Iterator<Item = TileType> {
+        registry().tile_types()
+    }
+
+    pub fn symbol(self) -> &'static str {
+        &registry().tile(self).glyph
+    }
+
+    // "Spider web" for "spider-web", for the editor which is not translated
+    pub fn name(self) -> String {
+        let name = self.id().replace('-', " ");
+        let mut chars = name.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => name,
+        }
+    }
+
+    pub fn id(self) -> &'static str {
+        &registry().tile(self).id
+    }
+
+    // Position in the registry, the same for every game with the same mods
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn from_id(id: &str) -> Option<TileType> {
+        registry().find_tile(id)
+    }
+
+    // The built-in tile type this one behaves like
+    pub fn like(self) -> TileType {
+        registry().tile(self).like
+    }
+
+    // Single ASCII character used to store the tile in a map file.
+    pub fn map_char(self) -> char {
+        registry().tile(self).map_char
+    }
+
+    pub fn from_map_char(c: char) -> Option<TileType> {
+        TileType::all().find(|tile| tile.map_char() == c)
+    }
+}
+
+impl Serialize for TileType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
 }
 
+impl<'de> Deserialize<'de> for TileType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TileType, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        TileType::from_id(&id).ok_or_else(|| D::Error::custom(format!("unknown tile '{}'", id)))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Terrain {
+    #[serde(serialize_with = "serialize_tiles")]
+    #[serde(deserialize_with = "deserialize_tiles")]
     pub tiles: [TileType; TERRAIN_WIDTH * TERRAIN_HEIGHT],
-    pub chest_found: bool,
+}
+
+// Tiles are saved as rows of map characters, like in map files
+fn serialize_tiles<S: Serializer>(
+    tiles: &[TileType; TERRAIN_WIDTH * TERRAIN_HEIGHT],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let rows: Vec<String> = tiles
+        .chunks(TERRAIN_WIDTH)
+        .map(|row| row.iter().map(|tile| tile.map_char()).collect())
+        .collect();
+    rows.serialize(serializer)
+}
+
+fn deserialize_tiles<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<[TileType; TERRAIN_WIDTH * TERRAIN_HEIGHT], D::Error> {
+    let rows = Vec::<String>::deserialize(deserializer)?;
+    if rows.len() != TERRAIN_HEIGHT {
+        return Err(D::Error::custom(format!(
+            "expected {} rows of tiles, found {}",
+            TERRAIN_HEIGHT,
+            rows.len()
+        )));
+    }
+    let mut tiles = [TileType::GRASS; TERRAIN_WIDTH * TERRAIN_HEIGHT];
+    for (y, row) in rows.iter().enumerate() {
+        if row.chars().count() != TERRAIN_WIDTH {
+            return Err(D::Error::custom(format!(
+                "expected {} tiles in row {}",
+                TERRAIN_WIDTH, y
+            )));
+        }
+        for (x, c) in row.chars().enumerate() {
+            tiles[y * TERRAIN_WIDTH + x] = TileType::from_map_char(c)
+                .ok_or_else(|| D::Error::custom(format!("unknown tile '{}'", c)))?;
+        }
+    }
+    Ok(tiles)
 }
 
 impl Terrain {
-    pub fn new() -> Self {
+    pub fn new<R: Rng>(rng: &mut R, balance: &Balance) -> Self {
+        let (forest_radius, pond_radius) = (balance.forest_radius, balance.pond_radius);
+
         // init terrain with grass tiles
         let mut terrain = Terrain {
-            tiles: [TileType::Grass; TERRAIN_WIDTH * TERRAIN_HEIGHT],
-            chest_found: false,
+            tiles: [TileType::GRASS; TERRAIN_WIDTH * TERRAIN_HEIGHT],
         };
 
-        let mut rng = rand::thread_rng();
-
         // generate mountains around the border
         for y in 0..TERRAIN_HEIGHT {
             for x in 0..TERRAIN_WIDTH {
                 if x == 0 || y == 0 || x == TERRAIN_WIDTH - 1 || y == TERRAIN_HEIGHT - 1 {
-                    terrain.tiles[y * TERRAIN_WIDTH + x] = TileType::Mountain;
+                    terrain.tiles[y * TERRAIN_WIDTH + x] = TileType::MOUNTAIN;
                 }
             }
         }
 
         // generate diamond forest
-        let center_forest_x = rng.gen_range(FOREST_RADIUS + 2..=TERRAIN_WIDTH - FOREST_RADIUS - 2);
-        let center_forest_y = rng.gen_range(FOREST_RADIUS + 2..=TERRAIN_HEIGHT - FOREST_RADIUS - 2);
+        let center_forest_x = rng.gen_range(forest_radius + 2..=TERRAIN_WIDTH - forest_radius - 2);
+        let center_forest_y = rng.gen_range(forest_radius + 2..=TERRAIN_HEIGHT - forest_radius - 2);
 
         for y in 0..TERRAIN_HEIGHT {
             for x in 0..TERRAIN_WIDTH {
                 let dx = (center_forest_x as isize - x as isize).abs();
                 let dy = (center_forest_y as isize - y as isize).abs();
                 // Manhattan distance for a diamond shape
-                if (dx + dy) as usize <= FOREST_RADIUS {
+                if (dx + dy) as usize <= forest_radius {
                     let index = y * TERRAIN_WIDTH + x;
-                    terrain.tiles[index] = TileType::Tree;
+                    terrain.tiles[index] = TileType::TREE;
                 }
             }
         }
@@ -63,24 +170,24 @@ impl Terrain {
         let mut is_overlapping;
 
         loop {
-            pond_center_x = rng.gen_range(POND_RADIUS + 4..=TERRAIN_WIDTH - POND_RADIUS - 4);
-            pond_center_y = rng.gen_range(POND_RADIUS + 4..=TERRAIN_HEIGHT - POND_RADIUS - 4);
+            pond_center_x = rng.gen_range(pond_radius + 4..=TERRAIN_WIDTH - pond_radius - 4);
+            pond_center_y = rng.gen_range(pond_radius + 4..=TERRAIN_HEIGHT - pond_radius - 4);
 
             // check for overlap
             is_overlapping = false;
-            for y in (pond_center_y.saturating_sub(POND_RADIUS))
-                ..=(pond_center_y + POND_RADIUS).min(TERRAIN_HEIGHT - 1)
+            for y in (pond_center_y.saturating_sub(pond_radius))
+                ..=(pond_center_y + pond_radius).min(TERRAIN_HEIGHT - 1)
             {
-                for x in (pond_center_x.saturating_sub(POND_RADIUS))
-                    ..=(pond_center_x + POND_RADIUS).min(TERRAIN_WIDTH - 1)
+                for x in (pond_center_x.saturating_sub(pond_radius))
+                    ..=(pond_center_x + pond_radius).min(TERRAIN_WIDTH - 1)
                 {
                     let dx = pond_center_x as isize - x as isize;
                     let dy = pond_center_y as isize - y as isize;
-                    if (dx * dx + dy * dy) as usize <= POND_RADIUS * POND_RADIUS {
+                    if (dx * dx + dy * dy) as usize <= pond_radius * pond_radius {
                         // if within the pond radius, check if also within the forest
                         let forest_dx = (center_forest_x as isize - x as isize).abs();
                         let forest_dy = (center_forest_y as isize - y as isize).abs();
-                        if (forest_dx + forest_dy) as usize <= FOREST_RADIUS {
+                        if (forest_dx + forest_dy) as usize <= forest_radius {
                             is_overlapping = true;
                             break; // break inner loop
                         }
@@ -102,11 +209,11 @@ impl Terrain {
                 let dx = pond_center_x as isize - x as isize;
                 let dy = pond_center_y as isize - y as isize;
                 // euclidean distance for a circle shape
-                if (dx * dx + dy * dy) as usize <= POND_RADIUS * POND_RADIUS {
+                if (dx * dx + dy * dy) as usize <= pond_radius * pond_radius {
                     let index = y * TERRAIN_WIDTH + x;
                     // only replace the tile if it's currently grass to avoid overwriting trees
-                    if let TileType::Grass = terrain.tiles[index] {
-                        terrain.tiles[index] = TileType::Water;
+                    if let TileType::GRASS = terrain.tiles[index] {
+                        terrain.tiles[index] = TileType::WATER;
                     }
                 }
             }
@@ -114,7 +221,7 @@ impl Terrain {
 
         // Add the castle tile in the middle of the pond
         let castle_index = pond_center_y * TERRAIN_WIDTH + pond_center_x;
-        terrain.tiles[castle_index] = TileType::Castle;
+        terrain.tiles[castle_index] = TileType::CASTLE;
 
         // Define a helper to check bounds and get tile indices
         let mut set_tile_if_in_bounds = |x, y, tile_type| {
@@ -132,24 +239,23 @@ impl Terrain {
                     set_tile_if_in_bounds(
                         pond_center_x.wrapping_add(i as usize),
                         pond_center_y.wrapping_add(j as usize),
-                        TileType::Grass,
+                        TileType::GRASS,
                     );
                 }
             }
         }
 
         // Place a layer of sand around the grass
-        for y in (pond_center_y.saturating_sub(POND_RADIUS + 2))
-            ..=(pond_center_y + POND_RADIUS + 2).min(TERRAIN_HEIGHT - 1)
+        for y in (pond_center_y.saturating_sub(pond_radius + 2))
+            ..=(pond_center_y + pond_radius + 2).min(TERRAIN_HEIGHT - 1)
         {
-            for x in (pond_center_x.saturating_sub(POND_RADIUS + 2))
-                ..=(pond_center_x + POND_RADIUS + 2).min(TERRAIN_WIDTH - 1)
+            for x in (pond_center_x.saturating_sub(pond_radius + 2))
+                ..=(pond_center_x + pond_radius + 2).min(TERRAIN_WIDTH - 1)
             {
                 // We are iterating in a square around the castle
                 // If it's not the center (castle) and not the immediate grass layer
-                if !(x == pond_center_x && y == pond_center_y)
-                    && !((x as isize - pond_center_x as isize).abs() <= 1
-                        && (y as isize - pond_center_y as isize).abs() <= 1)
+                if !((x as isize - pond_center_x as isize).abs() <= 1
+                    && (y as isize - pond_center_y as isize).abs() <= 1)
                 {
                     // We check if it's adjacent to a grass tile that is adjacent to the castle
                     let mut adjacent_to_grass = false;
@@ -159,7 +265,7 @@ impl Terrain {
                             let adjacent_y = y.wrapping_add(j as usize);
                             if adjacent_x < TERRAIN_WIDTH && adjacent_y < TERRAIN_HEIGHT {
                                 let adjacent_index = adjacent_y * TERRAIN_WIDTH + adjacent_x;
-                                if terrain.tiles[adjacent_index] == TileType::Grass
+                                if terrain.tiles[adjacent_index] == TileType::GRASS
                                     && ((adjacent_x as isize - pond_center_x as isize).abs() <= 1
                                         && (adjacent_y as isize - pond_center_y as isize).abs()
                                             <= 1)
@@ -176,18 +282,18 @@ impl Terrain {
 
                     if adjacent_to_grass {
                         let index = y * TERRAIN_WIDTH + x;
-                        terrain.tiles[index] = TileType::Sand;
+                        terrain.tiles[index] = TileType::SAND;
                     }
                 }
             }
         }
 
         // Add a sand layer around the pond
-        for y in (pond_center_y.saturating_sub(POND_RADIUS + 1))
-            ..=(pond_center_y + POND_RADIUS + 1).min(TERRAIN_HEIGHT - 1)
+        for y in (pond_center_y.saturating_sub(pond_radius + 1))
+            ..=(pond_center_y + pond_radius + 1).min(TERRAIN_HEIGHT - 1)
         {
-            for x in (pond_center_x.saturating_sub(POND_RADIUS + 1))
-                ..=(pond_center_x + POND_RADIUS + 1).min(TERRAIN_WIDTH - 1)
+            for x in (pond_center_x.saturating_sub(pond_radius + 1))
+                ..=(pond_center_x + pond_radius + 1).min(TERRAIN_WIDTH - 1)
             {
                 let dx = pond_center_x as isize - x as isize;
                 let dy = pond_center_y as isize - y as isize;
@@ -195,16 +301,169 @@ impl Terrain {
                 let index = y * TERRAIN_WIDTH + x;
 
                 // Check for a ring around the pond to place sand
-                if distance_squared as usize > POND_RADIUS * POND_RADIUS
-                    && distance_squared as usize <= (POND_RADIUS + 1) * (POND_RADIUS + 1)
-                    && (terrain.tiles[index] == TileType::Grass
-                        || terrain.tiles[index] == TileType::Water)
+                if distance_squared as usize > pond_radius * pond_radius
+                    && distance_squared as usize <= (pond_radius + 1) * (pond_radius + 1)
+                    && (terrain.tiles[index] == TileType::GRASS
+                        || terrain.tiles[index] == TileType::WATER)
                 {
-                    terrain.tiles[index] = TileType::Sand;
+                    terrain.tiles[index] = TileType::SAND;
                 }
             }
         }
 
         terrain
     }
+    // The largest area the player can walk across without an item, moving in
+    // all eight directions. The castle island is not part of it.
+    pub fn mainland(&self) -> Vec<bool> {
+        let mut area = vec![0; self.tiles.len()];
+        let mut sizes = vec![0];
+        for start in 0..self.tiles.len() {
+            if area[start] != 0 || !on_foot(self.tiles[start]) {
+                continue;
+            }
+            let id = sizes.len();
+            let mut size = 0;
+            area[start] = id;
+            let mut stack = vec![start];
+            while let Some(index) = stack.pop() {
+                size += 1;
+                let (x, y) = (
+                    (index % TERRAIN_WIDTH) as i32,
+                    (index / TERRAIN_WIDTH) as i32,
+                );
+                for (dx, dy) in [
+                    (-1, -1),
+                    (0, -1),
+                    (1, -1),
+                    (-1, 0),
+                    (1, 0),
+                    (-1, 1),
+                    (0, 1),
+                    (1, 1),
+                ] {
+                    let (x, y) = (x + dx, y + dy);
+                    if x < 0 || y < 0 || x >= TERRAIN_WIDTH as i32 || y >= TERRAIN_HEIGHT as i32 {
+                        continue;
+                    }
+                    let next = y as usize * TERRAIN_WIDTH + x as usize;
+                    if area[next] == 0 && on_foot(self.tiles[next]) {
+                        area[next] = id;
+                        stack.push(next);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+        let largest = (1..sizes.len()).max_by_key(|&id| sizes[id]).unwrap_or(0);
+        area.iter().map(|&id| id != 0 && id == largest).collect()
+    }
+    // Replace the 4-connected area of identical tiles starting at (x, y) with `tile_type`.
+    // Returns the number of tiles that changed.
+    pub fn flood_fill(&mut self, x: usize, y: usize, tile_type: TileType) -> usize {
+        let target = self.tiles[y * TERRAIN_WIDTH + x];
+        if target == tile_type {
+            return 0;
+        }
+
+        let mut filled = 0;
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            let index = y * TERRAIN_WIDTH + x;
+            if self.tiles[index] != target {
+                continue;
+            }
+            self.tiles[index] = tile_type;
+            filled += 1;
+
+            if x > 0 {
+                stack.push((x - 1, y));
+            }
+            if x + 1 < TERRAIN_WIDTH {
+                stack.push((x + 1, y));
+            }
+            if y > 0 {
+                stack.push((x, y - 1));
+            }
+            if y + 1 < TERRAIN_HEIGHT {
+                stack.push((x, y + 1));
+            }
+        }
+
+        filled
+    }
+}
+
+// Whether the player walks on the tile without an item
+pub fn on_foot(tile: TileType) -> bool {
+    matches!(
+        tile.like(),
+        TileType::GRASS | TileType::SAND | TileType::SHALLOW_WATER
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grass() -> Terrain {
+        Terrain {
+            tiles: [TileType::GRASS; TERRAIN_WIDTH * TERRAIN_HEIGHT],
+        }
+    }
+
+    #[test]
+    fn flood_fill_stays_in_bounds() {
+        // from every corner, the whole map and nothing past its edges
+        for (x, y) in [
+            (0, 0),
+            (TERRAIN_WIDTH - 1, 0),
+            (0, TERRAIN_HEIGHT - 1),
+            (TERRAIN_WIDTH - 1, TERRAIN_HEIGHT - 1),
+        ] {
+            let mut terrain = grass();
+            let filled = terrain.flood_fill(x, y, TileType::SAND);
+            assert_eq!(filled, TERRAIN_WIDTH * TERRAIN_HEIGHT);
+            assert!(terrain.tiles.iter().all(|&tile| tile == TileType::SAND));
+        }
+    }
+
+    #[test]
+    fn flood_fill_stops_at_other_tiles() {
+        let mut terrain = grass();
+        // a wall of trees down the third column
+        for y in 0..TERRAIN_HEIGHT {
+            terrain.tiles[y * TERRAIN_WIDTH + 2] = TileType::TREE;
+        }
+        assert_eq!(
+            terrain.flood_fill(0, 0, TileType::WATER),
+            2 * TERRAIN_HEIGHT
+        );
+        assert!(terrain.tiles[TERRAIN_WIDTH + 3] == TileType::GRASS);
+        assert!(terrain.tiles[TERRAIN_WIDTH + 2] == TileType::TREE);
+        assert_eq!(terrain.flood_fill(1, 1, TileType::WATER), 0);
+    }
+
+    #[test]
+    fn mainland_is_the_largest_area_on_foot() {
+        let mut terrain = grass();
+        // mountains down the third column, but for a diagonal gap the player
+        // squeezes through in the first rows
+        for y in 1..TERRAIN_HEIGHT {
+            terrain.tiles[y * TERRAIN_WIDTH + 2] = TileType::MOUNTAIN;
+        }
+        terrain.tiles[1] = TileType::MOUNTAIN;
+        terrain.tiles[3] = TileType::MOUNTAIN;
+        terrain.tiles[TERRAIN_WIDTH + 3] = TileType::SHALLOW_WATER;
+        terrain.tiles[TERRAIN_WIDTH + 4] = TileType::WATER;
+        let mainland = terrain.mainland();
+        assert!(mainland[0] && mainland[2] && mainland[TERRAIN_WIDTH + 3]);
+        assert!(!mainland[1] && !mainland[TERRAIN_WIDTH + 2] && !mainland[TERRAIN_WIDTH + 4]);
+
+        // closed, the wall leaves the two columns on the left an island
+        terrain.tiles[2] = TileType::MOUNTAIN;
+        let mainland = terrain.mainland();
+        assert!(!mainland[0] && !mainland[TERRAIN_WIDTH]);
+        assert!(mainland[TERRAIN_WIDTH + 3] && mainland[TERRAIN_WIDTH - 1]);
+    }
 }
@@ -0,0 +1,199 @@
+use crate::world::terrain::{TileType, TERRAIN_HEIGHT, TERRAIN_WIDTH};
+use crate::world::World;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// Chance in percent, every turn, that the weather changes
+const WEATHER_CHANGE_CHANCE: u32 = 3;
+// Chance in per mille, every rainy turn, that a sand tile next to the water gets flooded
+const FLOOD_CHANCE: u32 = 20;
+// Chance in per mille, every dry turn, that a flooded tile dries up
+const DRY_CHANCE: u32 = 50;
+// Chance in per mille, every stormy turn, that a tree is knocked down
+const TREE_FALL_CHANCE: u32 = 3;
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Fog,
+    Storm,
+}
+
+impl Weather {
+    // The weather that may follow this one, with the odds of each
+    fn transitions(self) -> &'static [(Weather, u32)] {
+        match self {
+            Weather::Clear => &[
+                (Weather::Rain, 40),
+                (Weather::Fog, 40),
+                (Weather::Storm, 20),
+            ],
+            Weather::Rain => &[
+                (Weather::Clear, 50),
+                (Weather::Storm, 30),
+                (Weather::Fog, 20),
+            ],
+            Weather::Fog => &[(Weather::Clear, 70), (Weather::Rain, 30)],
+            Weather::Storm => &[(Weather::Rain, 70), (Weather::Clear, 30)],
+        }
+    }
+
+    pub fn next<R: Rng>(self, rng: &mut R) -> Weather {
+        if rng.gen_range(0..100) >= WEATHER_CHANGE_CHANCE {
+            return self;
+        }
+        let transitions = self.transitions();
+        let total: u32 = transitions.iter().map(|(_, odds)| odds).sum();
+        let mut roll = rng.gen_range(0..total);
+        for &(weather, odds) in transitions {
+            if roll < odds {
+                return weather;
+            }
+            roll -= odds;
+        }
+        self
+    }
+
+    pub fn is_raining(self) -> bool {
+        matches!(self, Weather::Rain | Weather::Storm)
+    }
+
+    // How far the player sees through it, in tiles. None means no limit.
+    pub fn sight_radius(self) -> Option<u32> {
+        match self {
+            Weather::Fog => Some(5),
+            Weather::Storm => Some(15),
+            Weather::Clear | Weather::Rain => None,
+        }
+    }
+
+    // Whether the weather is drawn over the tile this turn. The pattern shifts
+    // every turn so the rain seems to fall.
+    pub fn covers(self, x: usize, y: usize, turn: u32) -> bool {
+        self != Weather::Clear && (x * 7 + y * 13 + turn as usize * 3).is_multiple_of(17)
+    }
+
+    pub fn message_id(self) -> &'static str {
+        match self {
+            Weather::Clear => "weather-clear",
+            Weather::Rain => "weather-rain",
+            Weather::Fog => "weather-fog",
+            Weather::Storm => "weather-storm",
+        }
+    }
+}
+
+// Let the weather evolve for one turn and apply its effects to the terrain
+pub fn update(world: &mut World) {
+    world.weather = world.weather.next(&mut world.rng);
+    // one of the trees hides the chest with the harpoon, they all stand until it is found
+    let chest_found = world
+        .terrain
+        .tiles
+        .iter()
+        .any(|tile| tile.like() == TileType::CHEST);
+
+    let mut changes = Vec::new();
+    for y in 0..TERRAIN_HEIGHT {
+        for x in 0..TERRAIN_WIDTH {
+            let roll = world.rng.gen_range(0..1000);
+            let change = match world.terrain.tiles[y * TERRAIN_WIDTH + x] {
+                // rain raises the water level at the pond edges
+                TileType::SAND
+                    if world.weather.is_raining()
+                        && roll < FLOOD_CHANCE
+                        && next_to_water(world, x, y) =>
+                {
+                    Some(TileType::SHALLOW_WATER)
+                }
+                // and the water slowly goes back down once it stops
+                TileType::SHALLOW_WATER if !world.weather.is_raining() && roll < DRY_CHANCE => {
+                    Some(TileType::SAND)
+                }
+                // storms knock down trees
+                TileType::TREE
+                    if world.weather == Weather::Storm
+                        && roll < TREE_FALL_CHANCE
+                        && chest_found =>
+                {
+                    Some(TileType::GRASS)
+                }
+                _ => None,
+            };
+            if let Some(tile_type) = change {
+                changes.push((x as i32, y as i32, tile_type));
+            }
+        }
+    }
+
+    for (x, y, tile_type) in changes {
+        world.set_tile(x, y, tile_type);
+    }
+}
+
+fn next_to_water(world: &World, x: usize, y: usize) -> bool {
+    [(0, -1), (1, 0), (0, 1), (-1, 0)].iter().any(|(dx, dy)| {
+        let (x, y) = (x as i32 + dx, y as i32 + dy);
+        x >= 0
+            && y >= 0
+            && x < TERRAIN_WIDTH as i32
+            && y < TERRAIN_HEIGHT as i32
+            && matches!(
+                world.terrain.tiles[y as usize * TERRAIN_WIDTH + x as usize].like(),
+                TileType::WATER | TileType::SHALLOW_WATER
+            )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot;
+    use crate::game::Content;
+    use crate::world::Balance;
+
+    fn trees(world: &World) -> usize {
+        world
+            .terrain
+            .tiles
+            .iter()
+            .filter(|&&tile| tile == TileType::TREE)
+            .count()
+    }
+
+    fn storm(world: &mut World, turns: u32) {
+        for _ in 0..turns {
+            world.weather = Weather::Storm;
+            update(world);
+        }
+    }
+
+    #[test]
+    fn storms_keep_the_game_winnable() {
+        let mut world = World::with_seed(4, Balance::default());
+        let before = trees(&world);
+        storm(&mut world, 3000);
+        assert_eq!(trees(&world), before);
+        world.weather = Weather::Clear;
+        assert!(bot::play(&mut world, &Content::default()));
+    }
+
+    #[test]
+    fn storms_knock_down_trees_once_the_chest_is_found() {
+        let mut world = World::with_seed(4, Balance::default());
+        let before = trees(&world);
+        let tree = world
+            .terrain
+            .tiles
+            .iter()
+            .position(|&tile| tile == TileType::TREE)
+            .unwrap();
+        world.terrain.tiles[tree] = TileType::CHEST;
+        storm(&mut world, 100);
+        assert!(trees(&world) < before - 1);
+    }
+}
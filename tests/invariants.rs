@@ -0,0 +1,112 @@
+use ascii_rpg::bot;
+use ascii_rpg::game::{self, Content, Turn};
+use ascii_rpg::gym::ACTIONS;
+use ascii_rpg::outcome::Outcome;
+use ascii_rpg::world::terrain::{TileType, TERRAIN_WIDTH};
+use ascii_rpg::world::{Balance, Difficulty, ItemType, NPCType, World};
+use proptest::prelude::*;
+
+// Random games played through the game core, checking after every turn that
+// the rules left the world in a state the game can be in
+
+// The turns of a random game: ACTIONS[n], or the move of the bot for None so
+// the games also get to the items, the water and the castle
+fn steps() -> impl Strategy<Value = Vec<Option<usize>>> {
+    prop::collection::vec(prop::option::of(0..ACTIONS.len()), 1..300)
+}
+
+// Items the player may start with besides the sword, to reach more of the rules
+const EXTRA_ITEMS: [ItemType; 4] = [
+    ItemType::AXE,
+    ItemType::HARPOON,
+    ItemType::SNORKEL,
+    ItemType::KEY,
+];
+
+fn tile_at(world: &World, x: i32, y: i32) -> TileType {
+    world.terrain.tiles[y as usize * TERRAIN_WIDTH + x as usize]
+}
+
+fn check(world: &World, outcome: &Option<Outcome>) -> Result<(), TestCaseError> {
+    let player = &world.player;
+    let tile = tile_at(world, player.x, player.y).like();
+    prop_assert!(tile != TileType::MOUNTAIN, "player on a mountain");
+    prop_assert!(
+        tile != TileType::WATER || player.has(ItemType::SNORKEL),
+        "player in the water without a snorkel"
+    );
+
+    // spiders weave webs on the tiles they leave, and never across the
+    // mainland where they can be met
+    let mainland = world.terrain.mainland();
+    for (index, npc) in world.npcs.iter().enumerate() {
+        let tile = tile_at(world, npc.x, npc.y);
+        prop_assert!(
+            tile == npc.npc_type.allowed_tile(),
+            "{} on {} at {},{}",
+            npc.npc_type.id(),
+            tile.id(),
+            npc.x,
+            npc.y
+        );
+        prop_assert!(
+            npc.npc_type.like() != NPCType::SPIDER
+                || mainland[npc.y as usize * TERRAIN_WIDTH + npc.x as usize],
+            "spider cut off at {},{}",
+            npc.x,
+            npc.y
+        );
+        prop_assert!(
+            world.npcs[index + 1..]
+                .iter()
+                .all(|other| (other.x, other.y) != (npc.x, npc.y)),
+            "two NPCs at {},{}",
+            npc.x,
+            npc.y
+        );
+    }
+
+    for (index, item) in player.inventory.iter().enumerate() {
+        prop_assert!(
+            !player.inventory[index + 1..].contains(item),
+            "two of the {}",
+            item.id()
+        );
+    }
+
+    prop_assert!(
+        !matches!(outcome, Some(Outcome::Unreachable)),
+        "unreachable outcome"
+    );
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn rules_keep_the_world_valid(
+        seed in any::<u64>(),
+        difficulty in 0..Difficulty::ALL.len(),
+        items in prop::sample::subsequence(vec![0, 1, 2, 3], 0..=EXTRA_ITEMS.len()),
+        steps in steps(),
+    ) {
+        let content = Content::default();
+        let mut world = World::with_seed(seed, Balance::preset(Difficulty::ALL[difficulty]));
+        for item in items {
+            world.give_item(EXTRA_ITEMS[item]);
+        }
+        check(&world, &None)?;
+        for step in steps {
+            let action = match step {
+                Some(index) => ACTIONS[index],
+                None => bot::next_action(&world),
+            };
+            let Turn { outcome, .. } = game::play_turn(&mut world, &content, action);
+            check(&world, &outcome)?;
+            if world.player.dead || world.stats.won_at.is_some() {
+                break;
+            }
+        }
+    }
+}
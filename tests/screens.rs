@@ -0,0 +1,210 @@
+use ascii_rpg::dev::Input;
+use ascii_rpg::game::{self, Content, Setup};
+use ascii_rpg::i18n::{Catalog, Language};
+use ascii_rpg::keybindings::KeyBindings;
+use ascii_rpg::menu::ConfigFiles;
+use ascii_rpg::profile::Profile;
+use ascii_rpg::settings::Settings;
+use ascii_rpg::tileset::Tileset;
+use ascii_rpg::world::terrain::{TileType, TERRAIN_WIDTH};
+use ascii_rpg::world::{Balance, ItemType, World};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use termion::event::Key;
+use vt100::{Callbacks, Color, Parser, Screen};
+
+// The game played in a VT100 emulator with scripted keys, checking the screen
+// it leaves against the snapshots of tests/snapshots. After a change to the
+// screens, write them again with:
+//
+//   UPDATE_SNAPSHOTS=1 cargo test --test screens
+
+const ROWS: u16 = 80;
+const COLS: u16 = 120;
+const SEED: u64 = 42;
+
+// How many times the terminal bell rang
+struct Bells(u32);
+
+impl Callbacks for Bells {
+    fn audible_bell(&mut self, _: &mut Screen) {
+        self.0 += 1;
+    }
+}
+
+// Play the world with the keys, on the ASCII tileset so every tile is a cell
+fn play(world: World, keys: &[Key]) -> Parser<Bells> {
+    let settings = Settings {
+        tileset: Tileset::Ascii,
+        ..Settings::default()
+    };
+    let files = ConfigFiles {
+        keybindings: PathBuf::from("missing-keybindings.toml"),
+        balance: PathBuf::from("missing-balance.toml"),
+        rules: PathBuf::from("missing-rules.toml"),
+        scripts: PathBuf::from("missing-scripts"),
+        mods: PathBuf::from("missing-mods"),
+        dev: false,
+    };
+    let setup = Setup {
+        settings: &settings,
+        keybindings: &KeyBindings::default(),
+        content: &Content::default(),
+        catalog: &Catalog::new(Language::English),
+        files: &files,
+    };
+    let mut output = Vec::new();
+    let mut keys = keys
+        .iter()
+        .map(|&key| Ok::<Input, io::Error>(Input::Key(key)));
+    game::start(
+        &mut output,
+        &mut keys,
+        world,
+        &setup,
+        &mut Profile::default(),
+    )
+    .unwrap();
+    let mut terminal = Parser::new_with_callbacks(ROWS, COLS, 0, Bells(0));
+    terminal.process(&output);
+    terminal
+}
+
+fn world() -> World {
+    let mut world = World::with_seed(SEED, Balance::default());
+    world.player.name = "Tester".to_string();
+    world
+}
+
+// Put the tile east of the player, where the Right key moves them
+fn east_of_player(world: &mut World, tile: TileType) -> (u16, u16) {
+    let (x, y) = (world.player.x + 1, world.player.y);
+    world.terrain.tiles[y as usize * TERRAIN_WIDTH + x as usize] = tile;
+    world.npcs.retain(|npc| (npc.x, npc.y) != (x, y));
+    (x as u16, y as u16)
+}
+
+// The text of the screen, then the colour of every cell, then the cursor
+fn snapshot(screen: &Screen) -> String {
+    let mut snapshot = String::new();
+    for row in screen.rows(0, COLS) {
+        snapshot.push_str(row.trim_end());
+        snapshot.push('\n');
+    }
+    snapshot.push_str("--- colours\n");
+    for row in 0..ROWS {
+        let colours: String = (0..COLS)
+            .map(|col| {
+                let cell = screen.cell(row, col).unwrap();
+                match cell.fgcolor() {
+                    _ if !cell.has_contents() => ' ',
+                    Color::Default if cell.bold() => 'B',
+                    Color::Default => '.',
+                    Color::Idx(index) => char::from_digit(index as u32 % 16, 16).unwrap(),
+                    Color::Rgb(..) => '#',
+                }
+            })
+            .collect();
+        snapshot.push_str(colours.trim_end());
+        snapshot.push('\n');
+    }
+    let (row, col) = screen.cursor_position();
+    snapshot.push_str(&format!("--- cursor {},{}\n", row, col));
+    snapshot
+}
+
+fn assert_snapshot(name: &str, screen: &Screen) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{}.txt", name));
+    let actual = snapshot(screen);
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_default();
+    if actual != expected {
+        let line = actual
+            .lines()
+            .zip(expected.lines())
+            .position(|(actual, expected)| actual != expected)
+            .unwrap_or(expected.lines().count());
+        panic!(
+            "the {} screen differs from {} at line {}:\n{}",
+            name,
+            path.display(),
+            line + 1,
+            actual.lines().nth(line).unwrap_or_default()
+        );
+    }
+}
+
+// The row and column of the tile on the screen, below the help panel
+fn cell_of(screen: &Screen, (x, y): (u16, u16)) -> (u16, u16) {
+    let map_top = (0..ROWS)
+        .find(|&row| screen.contents_between(row, 0, row, COLS).starts_with('^'))
+        .unwrap();
+    (map_top + y, x)
+}
+
+fn row_with(screen: &Screen, text: &str) -> Option<u16> {
+    (0..ROWS).find(|&row| screen.contents_between(row, 0, row, COLS).contains(text))
+}
+
+#[test]
+fn start_screen() {
+    let world = world();
+    let player = (world.player.x as u16, world.player.y as u16);
+    let terminal = play(world, &[]);
+    let screen = terminal.screen();
+    assert_snapshot("start", screen);
+
+    let (row, col) = cell_of(screen, player);
+    let cell = screen.cell(row, col).unwrap();
+    assert_eq!(cell.contents(), "@");
+    assert!(cell.bold());
+    let welcome = row_with(screen, "You are in a bizarre world").unwrap();
+    assert!(welcome > row);
+    assert_eq!(screen.cursor_position(), (welcome + 1, 0));
+    assert_eq!(terminal.callbacks().0, 0);
+}
+
+#[test]
+fn death_screen() {
+    let mut world = world();
+    let web = east_of_player(&mut world, TileType::SPIDER_WEB);
+    let terminal = play(world, &[Key::Right]);
+    let screen = terminal.screen();
+    assert_snapshot("death", screen);
+
+    // caught at the edge of the web
+    let (row, col) = cell_of(screen, web);
+    assert_eq!(screen.cell(row, col).unwrap().contents(), "#");
+    assert_eq!(screen.cell(row, col - 1).unwrap().contents(), "@");
+    // warnings are magenta
+    let message = row_with(screen, "It's a trap!!!").unwrap();
+    let text = screen.contents_between(message, 0, message, COLS);
+    let col = text.find("It's").unwrap() as u16;
+    assert_eq!(screen.cell(message, col).unwrap().fgcolor(), Color::Idx(13));
+    assert_eq!(terminal.callbacks().0, 1);
+}
+
+#[test]
+fn victory_screen() {
+    let mut world = world();
+    world.give_item(ItemType::KEY);
+    let castle = east_of_player(&mut world, TileType::CASTLE);
+    let terminal = play(world, &[Key::Right]);
+    let screen = terminal.screen();
+    assert_snapshot("victory", screen);
+
+    // the castle turned into a heart, the player did not move onto it
+    let (row, col) = cell_of(screen, castle);
+    assert_eq!(screen.cell(row, col).unwrap().contents(), "H");
+    assert_eq!(screen.cell(row, col - 1).unwrap().contents(), "@");
+    assert!(row_with(screen, "You enter the castle").is_some());
+    assert_eq!(terminal.callbacks().0, 1);
+}